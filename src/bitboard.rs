@@ -0,0 +1,372 @@
+//! Bitboards: sets of squares packed into a `u64`, with a1 as bit 0, h1 as bit 7 and h8 as
+//! bit 63, and the precomputed attack tables move generation is built on.
+//! Sliding attacks are looked up with magic bitboards, using the magic numbers below.
+//! The tables are filled the first time they are used.
+
+use crate::{coord::Coord, player::Player};
+use std::sync::LazyLock;
+
+pub type Bitboard = u64;
+
+pub const RANK_1: Bitboard = 0xff;
+pub const RANK_8: Bitboard = 0xff << 56;
+const FILE_A: Bitboard = 0x0101_0101_0101_0101;
+const FILE_H: Bitboard = FILE_A << 7;
+
+/// Index of the square in a bitboard, from 0 (a1) to 63 (h8)
+pub fn square_index(coord: Coord) -> usize {
+    (coord.row * 8 + coord.col) as usize
+}
+
+pub fn index_coord(square: usize) -> Coord {
+    Coord {
+        row: (square / 8) as i32,
+        col: (square % 8) as i32,
+    }
+}
+
+/// Bitboard with only `coord` set
+pub fn bit(coord: Coord) -> Bitboard {
+    1 << square_index(coord)
+}
+
+/// Iterates over the squares of a bitboard, from a1 to h8
+pub fn squares(mut bitboard: Bitboard) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if bitboard == 0 {
+            return None;
+        }
+        let square = bitboard.trailing_zeros() as usize;
+        bitboard &= bitboard - 1;
+        Some(square)
+    })
+}
+
+pub fn knight_attacks(square: usize) -> Bitboard {
+    TABLES.knight[square]
+}
+
+pub fn king_attacks(square: usize) -> Bitboard {
+    TABLES.king[square]
+}
+
+/// Squares a pawn of `player` standing on `square` attacks
+pub fn pawn_attacks(square: usize, player: Player) -> Bitboard {
+    TABLES.pawn[player as usize][square]
+}
+
+pub fn rook_attacks(square: usize, occupancy: Bitboard) -> Bitboard {
+    TABLES.rook[square].attacks(&TABLES.slider_attacks, occupancy)
+}
+
+pub fn bishop_attacks(square: usize, occupancy: Bitboard) -> Bitboard {
+    TABLES.bishop[square].attacks(&TABLES.slider_attacks, occupancy)
+}
+
+pub fn queen_attacks(square: usize, occupancy: Bitboard) -> Bitboard {
+    rook_attacks(square, occupancy) | bishop_attacks(square, occupancy)
+}
+
+/// Squares strictly between two squares on the same rank, file or diagonal, empty
+/// otherwise
+pub fn between(a: usize, b: usize) -> Bitboard {
+    TABLES.between[a][b]
+}
+
+/// The whole rank, file or diagonal through two squares, empty if they aren't aligned
+pub fn line(a: usize, b: usize) -> Bitboard {
+    TABLES.line[a][b]
+}
+
+const ROOK_DIRECTIONS: [Coord; 4] = Coord::LIST_CARDINAL;
+const BISHOP_DIRECTIONS: [Coord; 4] = Coord::LIST_DIAGONAL;
+
+/// Attacks of a slider on `square` found by walking each direction until a blocker,
+/// used to fill the magic tables
+fn sliding_attacks(square: usize, occupancy: Bitboard, directions: &[Coord]) -> Bitboard {
+    let mut attacks = 0;
+    for &dir in directions {
+        let mut coord = index_coord(square) + dir;
+        while coord.is_valid() {
+            attacks |= bit(coord);
+            if occupancy & bit(coord) != 0 {
+                break;
+            }
+            coord = coord + dir;
+        }
+    }
+    attacks
+}
+
+fn step_attacks(square: usize, deltas: &[Coord]) -> Bitboard {
+    deltas
+        .iter()
+        .map(|&delta| index_coord(square) + delta)
+        .filter(|coord| coord.is_valid())
+        .fold(0, |attacks, coord| attacks | bit(coord))
+}
+
+struct Magic {
+    mask: Bitboard,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    fn index(&self, occupancy: Bitboard) -> usize {
+        self.offset + ((occupancy & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+
+    fn attacks(&self, table: &[Bitboard], occupancy: Bitboard) -> Bitboard {
+        table[self.index(occupancy)]
+    }
+}
+
+struct Tables {
+    knight: [Bitboard; 64],
+    king: [Bitboard; 64],
+    pawn: [[Bitboard; 64]; 2],
+    rook: Vec<Magic>,
+    bishop: Vec<Magic>,
+    slider_attacks: Vec<Bitboard>,
+    between: Box<[[Bitboard; 64]; 64]>,
+    line: Box<[[Bitboard; 64]; 64]>,
+}
+
+static TABLES: LazyLock<Tables> = LazyLock::new(Tables::new);
+
+impl Tables {
+    fn new() -> Tables {
+        let mut knight = [0; 64];
+        let mut king = [0; 64];
+        let mut pawn = [[0; 64]; 2];
+        for square in 0..64 {
+            knight[square] = step_attacks(square, &Coord::LIST_KNIGHT);
+            king[square] = step_attacks(square, &Coord::LIST_CARDINAL_DIAGONAL);
+            for player in [Player::Black, Player::White] {
+                let forward = player.advancing_direction();
+                pawn[player as usize][square] =
+                    step_attacks(square, &[forward + Coord::L, forward + Coord::R]);
+            }
+        }
+
+        let mut slider_attacks = Vec::new();
+        let rook = (0..64)
+            .map(|square| {
+                init_magic(
+                    square,
+                    &ROOK_DIRECTIONS,
+                    ROOK_MAGICS[square],
+                    &mut slider_attacks,
+                )
+            })
+            .collect();
+        let bishop = (0..64)
+            .map(|square| {
+                init_magic(
+                    square,
+                    &BISHOP_DIRECTIONS,
+                    BISHOP_MAGICS[square],
+                    &mut slider_attacks,
+                )
+            })
+            .collect();
+
+        let mut between = Box::new([[0; 64]; 64]);
+        let mut line = Box::new([[0; 64]; 64]);
+        for a in 0..64 {
+            for &dir in ROOK_DIRECTIONS.iter().chain(&BISHOP_DIRECTIONS) {
+                let ray = sliding_attacks(a, 0, &[dir]);
+                let full_line = ray | sliding_attacks(a, 0, &[-1 * dir]) | 1 << a;
+
+                let mut squares_between = 0;
+                let mut coord = index_coord(a) + dir;
+                while coord.is_valid() {
+                    let b = square_index(coord);
+                    between[a][b] = squares_between;
+                    line[a][b] = full_line;
+                    squares_between |= 1 << b;
+                    coord = coord + dir;
+                }
+            }
+        }
+
+        Tables {
+            knight,
+            king,
+            pawn,
+            rook,
+            bishop,
+            slider_attacks,
+            between,
+            line,
+        }
+    }
+}
+
+/// Multipliers that map every relevant occupancy of a square to its own slot of the attack
+/// table, found by trial and error with sparse random numbers
+const ROOK_MAGICS: [u64; 64] = [
+    0x1080004008801020,
+    0x0840092002c03000,
+    0x1900200010400900,
+    0x0880100008000480,
+    0x4200100420080200,
+    0x8100020100080400,
+    0x0200040110886200,
+    0x0200008040220411,
+    0x0404800084400220,
+    0x0000401000402000,
+    0x0086001081220440,
+    0x0408800800100280,
+    0x000a001201040820,
+    0x8848800200840080,
+    0x4001000100040200,
+    0x0442000102105084,
+    0x9080010020804100,
+    0x0040404000201009,
+    0x0000808010002009,
+    0x2200090021d00100,
+    0x0008008008040080,
+    0x0004004002010040,
+    0x0011040008015042,
+    0x00000a0001768104,
+    0x0000800080204009,
+    0x2010004140002001,
+    0x9800200280100080,
+    0x1000100080080080,
+    0x0442000a00049020,
+    0x2100040080020080,
+    0x0800120400900148,
+    0x0010040a00128541,
+    0x2800804000800030,
+    0x1010002000400041,
+    0x4000200011004100,
+    0x0610008410800800,
+    0x0400802402800800,
+    0xc100020080800400,
+    0x0002000802000401,
+    0x0182085882000401,
+    0x0220204000808000,
+    0x2860100040024022,
+    0x0001002004110040,
+    0x99101042000a0020,
+    0x0004080004008080,
+    0x0010040002008080,
+    0x2012004881020004,
+    0x8300842444820011,
+    0x0088403882010200,
+    0x0820400080210100,
+    0x0110910040a00300,
+    0x0801100280080480,
+    0x0242009008200600,
+    0x1002000489500200,
+    0x0040800200010080,
+    0x0091800041000080,
+    0x0000209300488001,
+    0x04c1002414824001,
+    0x020020000b001041,
+    0x7000100004200901,
+    0x8002002004100802,
+    0x30010002084c0007,
+    0x0888221800813004,
+    0x4000002840840112,
+];
+
+const BISHOP_MAGICS: [u64; 64] = [
+    0xa010041108003100,
+    0x006082020a002900,
+    0x6810010619200000,
+    0x08281a0520000408,
+    0x0001104001000400,
+    0x0018901008048400,
+    0x00040a0210245280,
+    0x000200210808a402,
+    0x9140048410821200,
+    0x0800091010820041,
+    0x20504804832202c0,
+    0x0100091401081000,
+    0x8021011140000012,
+    0x0810020804450400,
+    0x208b0542109008a2,
+    0x0080084a08040204,
+    0x0040e2a80811244c,
+    0x2505022008008108,
+    0x0430220100420040,
+    0x010a040420220040,
+    0x1105000290400000,
+    0x0093001200822120,
+    0x4000a62048043004,
+    0x280120048a015004,
+    0x006090002a020814,
+    0x44042000240800d0,
+    0x01102800040a4400,
+    0x1004080080220040,
+    0x0001001011004024,
+    0x0010044000805040,
+    0x0914041200820100,
+    0x0004821012821480,
+    0x0024040500c05021,
+    0x0088611002080200,
+    0x0116080a00040020,
+    0x4000020080080080,
+    0x2450450140840040,
+    0x0000880201484100,
+    0x0222020404020092,
+    0x8081110600002e00,
+    0x2842101105000801,
+    0x1100809008001025,
+    0x00020202221c0400,
+    0x0422014022009020,
+    0x0210046102100c00,
+    0xc004008082029102,
+    0x00aa461801101200,
+    0x0404080080201108,
+    0x020542108c205002,
+    0x0410544804100100,
+    0x0040910841100000,
+    0x0400200042021100,
+    0x00004204850400c0,
+    0x0200100410a42102,
+    0x1040020801210102,
+    0x0805040410420000,
+    0x2884804130100200,
+    0x800c262201242000,
+    0x1058000194108800,
+    0x0014221054420204,
+    0x0104000012a02200,
+    0x0200881003300100,
+    0x0140400202840100,
+    0x0402020801010201,
+];
+
+/// Builds the lookup for a slider on `square`, appending its attack table to `table`
+fn init_magic(square: usize, directions: &[Coord], magic: u64, table: &mut Vec<Bitboard>) -> Magic {
+    // Blockers on the edge of the board never change the attacks, so leave them out
+    let coord = index_coord(square);
+    let edges = ((RANK_1 | RANK_8) & !(RANK_1 << (8 * coord.row)))
+        | ((FILE_A | FILE_H) & !(FILE_A << coord.col));
+    let mask = sliding_attacks(square, 0, directions) & !edges;
+    let bits = mask.count_ones();
+
+    let magic = Magic {
+        mask,
+        magic,
+        shift: 64 - bits,
+        offset: table.len(),
+    };
+    table.resize(table.len() + (1 << bits), 0);
+
+    // Every subset of the mask, enumerated with the carry-rippler trick
+    let mut subset: Bitboard = 0;
+    loop {
+        table[magic.index(subset)] = sliding_attacks(square, subset, directions);
+        subset = subset.wrapping_sub(mask) & mask;
+        if subset == 0 {
+            break;
+        }
+    }
+
+    magic
+}
@@ -1,20 +1,60 @@
+use crate::bitboard::{
+    between, bishop_attacks, bit, index_coord, king_attacks, knight_attacks, line, pawn_attacks,
+    queen_attacks, rook_attacks, square_index, squares, Bitboard, RANK_1, RANK_8,
+};
 use crate::{
     coord::Coord,
+    fen_error::FenError,
+    move_list::MoveList,
     piece::{Kind, Piece},
     player::Player,
     ply::Ply,
     status::Status,
+    zobrist::KEYS,
 };
-use auto_enums::auto_enum;
 use rayon::prelude::*;
 
+/// Everything `Board::make_move_mut` changes that can't be worked out from the move itself,
+/// so `Board::unmake_move` can restore the previous position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Undo {
+    ply: Ply,
+    captured: Option<Piece>,
+    castling_rights: [bool; 4],
+    en_passant_square: Option<Coord>,
+    half_move_clock: u64,
+    hash: u64,
+    castling: Option<Castling>,
+}
+
+/// Where the king and rook go when castling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Castling {
+    pub(crate) kingside: bool,
+    pub(crate) king_destination: Coord,
+    pub(crate) rook_origin: Coord,
+    pub(crate) rook_destination: Coord,
+}
+
+/// Selects a subset of the legal moves in `Board::get_moves`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveFilter {
+    All,
+    /// Captures (including en passant) and promotions
+    Captures,
+    /// Everything that is not in `Captures`
+    Quiet,
+    /// Moves that put the opponent in check
+    Checks,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct Board {
     pub turn: Player,
 
     pub board: [[Option<Piece>; 8]; 8],
-    pub white_pieces: Vec<Piece>,
-    pub black_pieces: Vec<Piece>,
+    pieces: [[Bitboard; 6]; 2], // Indexed by player, then kind
+    occupancy: [Bitboard; 2],   // Every piece of each player
     pub white_king_loc: Coord,
     pub black_king_loc: Coord,
 
@@ -22,15 +62,19 @@ pub struct Board {
     white_can_ooo: bool,
     black_can_oo: bool,
     black_can_ooo: bool,
-    half_move_clock: u64, // Counts the number of moves in a row without pawn moves or capture
+    rook_files: [[i32; 2]; 2], // Home file of the castling rooks, by player, then O-O and O-O-O
+    chess960: bool,            // Castling is written as the king taking its own rook
+    half_move_clock: u64,      // Counts the number of moves in a row without pawn moves or capture
+    full_move_number: u64,     // Starts at 1 and is incremented after every move by Black
     en_passant_square: Option<Coord>,
+    hash: u64, // Zobrist hash, kept up to date incrementally by make_move
 }
 
 impl Board {
     pub fn notation_conversion(v1: char, v2: i32) -> Option<Coord> {
-        if (v1 as i32) >= ('a' as i32) && (v1 as i32) <= ('h' as i32) && v2 >= 1 && v2 <= 8 {
+        if (v1 as i32) >= ('a' as i32) && (v1 as i32) <= ('h' as i32) && (1..=8).contains(&v2) {
             Some(Coord {
-                row: v2 - 1 as i32,
+                row: v2 - 1,
                 col: (v1 as i32) - ('a' as i32),
             })
         } else {
@@ -42,28 +86,83 @@ impl Board {
         let row = coord.row + 1;
         let col = coord.col + ('a' as i32);
 
-        if row >= 1 && row <= 8 && col >= ('a' as i32) && col <= ('h' as i32) {
+        if (1..=8).contains(&row) && col >= ('a' as i32) && col <= ('h' as i32) {
             (col as u8 as char, row)
         } else {
             ('a', 0)
         }
     }
 
+    /// Parses a FEN string, panicking if it is invalid.
+    /// Use `Board::from_fen` for input that isn't known to be valid
     pub fn new_from_fen(fen: &str) -> Board {
+        match Board::from_fen(fen) {
+            Ok(board) => board,
+            Err(error) => panic!("invalid FEN \"{fen}\": {error}"),
+        }
+    }
+
+    /// Starting position number `index` of Chess960, from 0 to 959 in the usual numbering,
+    /// where 518 is the standard starting position
+    pub fn new_chess960(index: usize) -> Board {
+        assert!(index < 960, "Chess960 positions are numbered from 0 to 959");
+        let mut back_rank = [None; 8];
+        let mut n = index;
+
+        // One bishop on each color, then the queen and knights on the free squares left,
+        // and the king between the rooks on the last three
+        back_rank[2 * (n % 4) + 1] = Some('B');
+        n /= 4;
+        back_rank[2 * (n % 4)] = Some('B');
+        n /= 4;
+        let mut place = |piece: char, nth_free: usize| {
+            let col = (0..8).filter(|&col| back_rank[col].is_none()).nth(nth_free);
+            back_rank[col.unwrap()] = Some(piece);
+        };
+        place('Q', n % 6);
+        n /= 6;
+        let knights = [
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 2),
+            (1, 3),
+            (1, 4),
+            (2, 3),
+            (2, 4),
+            (3, 4),
+        ];
+        let (first, second) = knights[n];
+        place('N', second);
+        place('N', first);
+        for piece in ['R', 'K', 'R'] {
+            place(piece, 0);
+        }
+
+        let white: String = back_rank.iter().flatten().collect();
+        let black = white.to_lowercase();
+        let mut board = Board::new_from_fen(&format!(
+            "{black}/pppppppp/8/8/8/8/PPPPPPPP/{white} w KQkq - 0 1"
+        ));
+        board.chess960 = true;
+        board
+    }
+
+    /// Parses a FEN string and checks that it describes a legal position
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
         let mut board: Board = Default::default();
 
-        let board_from_fen = fen::BoardState::from_fen(fen).unwrap();
+        check_fen_syntax(fen)?;
+        let board_from_fen = fen::BoardState::from_fen(fen)?;
 
         board.turn = match board_from_fen.side_to_play {
             fen::Color::White => Player::White,
             fen::Color::Black => Player::Black,
         };
 
-        board.white_can_oo = board_from_fen.white_can_oo;
-        board.white_can_ooo = board_from_fen.white_can_ooo;
-        board.black_can_oo = board_from_fen.black_can_oo;
-        board.black_can_ooo = board_from_fen.black_can_ooo;
         board.half_move_clock = board_from_fen.halfmove_clock;
+        board.full_move_number = board_from_fen.fullmove_number;
 
         board.en_passant_square = board_from_fen.en_passant_square.map(|x| Coord {
             row: (x / 8) as i32,
@@ -74,8 +173,7 @@ impl Board {
             .pieces
             .into_iter()
             .enumerate()
-            .map(|(i, p)| p.map(|x| (i, x)))
-            .flatten()
+            .filter_map(|(i, p)| p.map(|x| (i, x)))
             .for_each(|(i, p)| {
                 let pos = Coord {
                     row: (i / 8) as i32,
@@ -109,7 +207,6 @@ impl Board {
                     kind,
                     player: color,
                     coord: pos,
-                    idx: 0,
                 };
 
                 board.add_piece_to_empty_square(piece);
@@ -133,7 +230,352 @@ impl Board {
 
         // Board::check_everything(&board);
 
-        board
+        // The fen crate only looks for `KQkq`, so read the castling rights ourselves
+        board.read_castling_rights(fen.split_whitespace().nth(2).unwrap_or("-"))?;
+
+        board.hash = board.compute_zobrist_hash();
+        board.validate()?;
+        Ok(board)
+    }
+
+    /// Reads the castling field of a FEN, in standard notation, X-FEN or Shredder-FEN.
+    /// `K` and `Q` stand for the outermost rook on either side of the king, and a file
+    /// letter for the rook on that file, which Chess960 needs when a side has two rooks
+    /// on the same side of the king.
+    /// Rights that don't match the pieces are left for `validate` to report
+    fn read_castling_rights(&mut self, field: &str) -> Result<(), FenError> {
+        self.rook_files = [[7, 0]; 2];
+        if field == "-" {
+            return Ok(());
+        }
+
+        for c in field.chars() {
+            let player = if c.is_ascii_uppercase() {
+                Player::White
+            } else {
+                Player::Black
+            };
+            let row = player.home_row();
+            let king_col = self.home_king_col(player).unwrap_or(4);
+            let mut rooks = (0..8).filter(|&col| {
+                self.get_piece_by_coord(Coord { row, col })
+                    .is_some_and(|p| p.kind == Kind::Rook && p.player == player)
+            });
+
+            let (side, rook_col) = match c.to_ascii_lowercase() {
+                'k' => (0, rooks.rfind(|&col| col > king_col).unwrap_or(7)),
+                'q' => (1, rooks.find(|&col| col < king_col).unwrap_or(0)),
+                file @ 'a'..='h' => {
+                    self.chess960 = true;
+                    let col = file as i32 - 'a' as i32;
+                    (usize::from(col < king_col), col)
+                }
+                _ => return Err(FenError::BadCastling(field.to_string())),
+            };
+
+            self.rook_files[player as usize][side] = rook_col;
+            *self.castling_right_mut(player, side) = true;
+            if king_col != 4 || rook_col != [7, 0][side] {
+                self.chess960 = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// File of `player`'s king if it stands on its home rank, where it has to be to castle
+    fn home_king_col(&self, player: Player) -> Option<i32> {
+        let home_rank = RANK_1 << (8 * player.home_row());
+        squares(self.bitboard(Kind::King, player) & home_rank)
+            .next()
+            .map(|square| index_coord(square).col)
+    }
+
+    /// Castling rights of `player`, for O-O and O-O-O
+    fn castling_rights(&self, player: Player) -> [bool; 2] {
+        match player {
+            Player::White => [self.white_can_oo, self.white_can_ooo],
+            Player::Black => [self.black_can_oo, self.black_can_ooo],
+        }
+    }
+
+    fn castling_right_mut(&mut self, player: Player, side: usize) -> &mut bool {
+        match (player, side) {
+            (Player::White, 0) => &mut self.white_can_oo,
+            (Player::White, _) => &mut self.white_can_ooo,
+            (Player::Black, 0) => &mut self.black_can_oo,
+            (Player::Black, _) => &mut self.black_can_ooo,
+        }
+    }
+
+    /// Whether this is a Chess960 position, where castling moves are written as the king
+    /// capturing its own rook
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    /// Switches between writing castling as the king taking its own rook and as the king
+    /// moving two squares. GUIs playing Chess960 set this even for positions that look
+    /// like standard chess, where the FEN alone can't tell
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960;
+    }
+
+    /// Checks the invariants a position loaded from FEN has to satisfy
+    fn validate(&self) -> Result<(), FenError> {
+        for player in [Player::White, Player::Black] {
+            match self.bitboard(Kind::King, player).count_ones() {
+                0 => return Err(FenError::MissingKing(player)),
+                1 => {}
+                _ => return Err(FenError::TooManyKings(player)),
+            }
+        }
+
+        let pawns =
+            self.bitboard(Kind::Pawn, Player::White) | self.bitboard(Kind::Pawn, Player::Black);
+        if let Some(square) = squares(pawns & (RANK_1 | RANK_8)).next() {
+            return Err(FenError::PawnOnBackRank(index_coord(square)));
+        }
+
+        // The king and the rook have to be on their home rank, with the rook on the side
+        // of the king it castles to
+        [
+            (Player::White, 0, 'K'),
+            (Player::White, 1, 'Q'),
+            (Player::Black, 0, 'k'),
+            (Player::Black, 1, 'q'),
+        ]
+        .into_iter()
+        .filter(|&(player, side, _)| self.castling_rights(player)[side])
+        .try_for_each(|(player, side, right)| {
+            let row = player.home_row();
+            let rook_col = self.rook_files[player as usize][side];
+            let rook = self.get_piece_by_coord(Coord { row, col: rook_col });
+
+            match self.home_king_col(player) {
+                Some(king_col)
+                    if rook.is_some_and(|p| p.kind == Kind::Rook && p.player == player)
+                        && (side == 0) == (rook_col > king_col) =>
+                {
+                    Ok(())
+                }
+                _ => Err(FenError::InconsistentCastlingRights(right)),
+            }
+        })?;
+
+        if let Some(en_passant_square) = self.en_passant_square {
+            // The pawn that just moved belongs to the side not to move
+            let pusher = self.turn.opponent();
+            let dir = pusher.advancing_direction();
+
+            // The row is checked first, so the squares around it are on the board
+            if en_passant_square.row != pusher.pawn_row() + dir.row
+                || self.is_square_occupied(en_passant_square)
+                || self.is_square_occupied(en_passant_square - dir)
+                || !self
+                    .get_piece_by_coord(en_passant_square + dir)
+                    .is_some_and(|p| p.kind == Kind::Pawn && p.player == pusher)
+            {
+                return Err(FenError::InconsistentEnPassant(en_passant_square));
+            }
+        }
+
+        if self.is_square_attacked(self.find_king(self.turn.opponent()), self.turn) {
+            return Err(FenError::OpponentInCheck);
+        }
+
+        Ok(())
+    }
+
+    /// Serializes the board back into Forsyth-Edwards notation
+    pub fn to_fen(&self) -> String {
+        let pieces = (0..64)
+            .map(|i| {
+                self.get_piece_by_coord(Coord {
+                    row: i / 8,
+                    col: i % 8,
+                })
+                .map(|p| fen::Piece {
+                    kind: match p.kind {
+                        Kind::Pawn => fen::PieceKind::Pawn,
+                        Kind::Knight => fen::PieceKind::Knight,
+                        Kind::Bishop => fen::PieceKind::Bishop,
+                        Kind::Rook => fen::PieceKind::Rook,
+                        Kind::Queen => fen::PieceKind::Queen,
+                        Kind::King => fen::PieceKind::King,
+                    },
+                    color: match p.player {
+                        Player::White => fen::Color::White,
+                        Player::Black => fen::Color::Black,
+                    },
+                })
+            })
+            .collect();
+
+        let fen = fen::BoardState {
+            pieces,
+            side_to_play: match self.turn {
+                Player::White => fen::Color::White,
+                Player::Black => fen::Color::Black,
+            },
+            white_can_oo: self.white_can_oo,
+            white_can_ooo: self.white_can_ooo,
+            black_can_oo: self.black_can_oo,
+            black_can_ooo: self.black_can_ooo,
+            en_passant_square: self
+                .en_passant_square
+                .map(|coord| (coord.row * 8 + coord.col) as u8),
+            halfmove_clock: self.half_move_clock,
+            fullmove_number: self.full_move_number,
+        }
+        .to_fen();
+
+        // Write the castling rights in X-FEN, which is plain FEN for standard chess
+        let castling = self.castling_fen();
+        let mut fields: Vec<&str> = fen.split(' ').collect();
+        fields[2] = &castling;
+        fields.join(" ")
+    }
+
+    /// `K`/`Q` for castling with the outermost rook on that side of the king, and the rook's
+    /// file otherwise
+    fn castling_fen(&self) -> String {
+        let mut field = String::new();
+        for player in [Player::White, Player::Black] {
+            let row = player.home_row();
+            for side in 0..2 {
+                if !self.castling_rights(player)[side] {
+                    continue;
+                }
+                let rook_col = self.rook_files[player as usize][side];
+                let outer_cols = if side == 0 {
+                    rook_col + 1..8
+                } else {
+                    0..rook_col
+                };
+                let is_outermost = !outer_cols.into_iter().any(|col| {
+                    self.get_piece_by_coord(Coord { row, col })
+                        .is_some_and(|p| p.kind == Kind::Rook && p.player == player)
+                });
+
+                let c = if is_outermost {
+                    ['K', 'Q'][side]
+                } else {
+                    (b'A' + rook_col as u8) as char
+                };
+                field.push(match player {
+                    Player::White => c,
+                    Player::Black => c.to_ascii_lowercase(),
+                });
+            }
+        }
+
+        if field.is_empty() {
+            field.push('-');
+        }
+        field
+    }
+
+    pub fn full_move_number(&self) -> u64 {
+        self.full_move_number
+    }
+
+    /// Plies since the last capture or pawn move
+    pub fn half_move_clock(&self) -> u64 {
+        self.half_move_clock
+    }
+
+    /// Zobrist hash of the position.
+    /// Positions with the same placement, side to move, castling rights and en passant
+    /// possibilities hash the same, regardless of the move clocks
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Computes the Zobrist hash from scratch
+    fn compute_zobrist_hash(&self) -> u64 {
+        let mut hash = self
+            .pieces(Player::White)
+            .chain(self.pieces(Player::Black))
+            .fold(0, |hash, p| hash ^ KEYS.piece(p.kind, p.player, p.coord));
+
+        if self.turn == Player::Black {
+            hash ^= KEYS.black_to_move;
+        }
+
+        hash ^ self.castling_hash() ^ self.en_passant_hash()
+    }
+
+    fn castling_hash(&self) -> u64 {
+        [
+            self.white_can_oo,
+            self.white_can_ooo,
+            self.black_can_oo,
+            self.black_can_ooo,
+        ]
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, can_castle)| can_castle)
+        .fold(0, |hash, (right, _)| hash ^ KEYS.castling(right))
+    }
+
+    /// The en passant square only contributes to the hash if a pawn of the side to move
+    /// stands next to the pawn that was just pushed
+    fn en_passant_hash(&self) -> u64 {
+        let Some(en_passant_square) = self.en_passant_square else {
+            return 0;
+        };
+
+        let pushed_pawn = en_passant_square - self.turn.advancing_direction();
+        let can_capture = [Coord::L, Coord::R]
+            .iter()
+            .map(|&dir| pushed_pawn + dir)
+            .filter(|c| c.is_valid())
+            .flat_map(|c| self.get_piece_by_coord(c))
+            .any(|p| p.kind == Kind::Pawn && p.player == self.turn);
+
+        if can_capture {
+            KEYS.en_passant_file(en_passant_square.col)
+        } else {
+            0
+        }
+    }
+
+    /// En passant square, but only if an en passant capture is actually legal
+    fn legal_en_passant_square(&self) -> Option<Coord> {
+        let square = self.en_passant_square?;
+        self.get_all_captures()
+            .any(|ply| {
+                ply.destination == square && self.kind_at_square(ply.origin) == Some(Kind::Pawn)
+            })
+            .then_some(square)
+    }
+
+    /// Whether both boards are the same position under the repetition rules: same pieces
+    /// on the same squares, side to move, castling rights and possible en passant captures.
+    /// Unlike `==`, the move clocks and the order of the piece lists don't matter
+    pub fn is_same_position(&self, other: &Board) -> bool {
+        let placement = |board: &Board| {
+            board
+                .board
+                .map(|row| row.map(|square| square.map(|piece| (piece.kind, piece.player))))
+        };
+
+        // The hash only tells whether en passant is pseudo-legal, so leave it out
+        self.hash ^ self.en_passant_hash() == other.hash ^ other.en_passant_hash()
+            && self.turn == other.turn
+            && (
+                self.white_can_oo,
+                self.white_can_ooo,
+                self.black_can_oo,
+                self.black_can_ooo,
+            ) == (
+                other.white_can_oo,
+                other.white_can_ooo,
+                other.black_can_oo,
+                other.black_can_ooo,
+            )
+            && placement(self) == placement(other)
+            && self.legal_en_passant_square() == other.legal_en_passant_square()
     }
 
     pub fn print_board(&self, player_pov: Player) {
@@ -154,79 +596,34 @@ impl Board {
     }
 
     /// Adds a piece to an empty square
-    /// Index management is done here
-    fn add_piece_to_empty_square(&mut self, mut p: Piece) {
+    fn add_piece_to_empty_square(&mut self, p: Piece) {
         assert!(self.board[p.coord.row as usize][p.coord.col as usize].is_none());
-        match p.player {
-            Player::Black => {
-                p.idx = self.black_pieces.len();
-                self.black_pieces.push(p);
-            }
-            Player::White => {
-                p.idx = self.white_pieces.len();
-                self.white_pieces.push(p);
-            }
-        }
         self.board[p.coord.row as usize][p.coord.col as usize] = Some(p);
+        self.toggle_bitboards(p);
     }
 
     /// Removes a piece from an occupied square by providing the coordinate
     /// Does nothing if the square is not occupied
-    /// Index management is done here
     fn remove_piece_from_occupied_square(&mut self, coord: Coord) {
         if let &Some(p) = self.get_piece_by_coord(coord) {
-            match p.player {
-                Player::Black => {
-                    self.black_pieces.remove(p.idx);
-                    for piece in self.black_pieces.iter_mut().skip(p.idx) {
-                        piece.idx -= 1;
-                        self.board[piece.coord.row as usize][piece.coord.col as usize]
-                            .iter_mut()
-                            .for_each(|x| x.idx -= 1);
-                    }
-                }
-                Player::White => {
-                    self.white_pieces.remove(p.idx);
-                    for piece in self.white_pieces.iter_mut().skip(p.idx) {
-                        piece.idx -= 1;
-                        self.board[piece.coord.row as usize][piece.coord.col as usize]
-                            .iter_mut()
-                            .for_each(|x| x.idx -= 1);
-                    }
-                }
-            };
             self.board[coord.row as usize][coord.col as usize] = None;
+            self.toggle_bitboards(p);
         }
     }
 
     fn promote_piece(&mut self, mut p: Piece, promo: Kind) {
-        match p.player {
-            Player::Black => {
-                p.kind = promo;
-                self.black_pieces[p.idx] = p;
-            }
-            Player::White => {
-                p.kind = promo;
-                self.white_pieces[p.idx] = p;
-            }
-        }
+        self.toggle_bitboards(p);
+        p.kind = promo;
+        self.toggle_bitboards(p);
         self.board[p.coord.row as usize][p.coord.col as usize] = Some(p);
     }
 
     fn move_piece_by_coord(&mut self, origin: Coord, destination: Coord) {
         if let &Some(mut p) = self.get_piece_by_coord(origin) {
             self.remove_piece_from_occupied_square(destination);
-            self.board[p.coord.row as usize][p.coord.col as usize] = None;
+            self.remove_piece_from_occupied_square(origin);
             p.coord = destination;
-            self.board[destination.row as usize][destination.col as usize] = Some(p);
-            match p.player {
-                Player::Black => {
-                    self.black_pieces[p.idx] = p;
-                }
-                Player::White => {
-                    self.white_pieces[p.idx] = p;
-                }
-            };
+            self.add_piece_to_empty_square(p);
             if p.kind == Kind::King {
                 match p.player {
                     Player::Black => self.black_king_loc = destination,
@@ -236,749 +633,639 @@ impl Board {
         }
     }
 
+    /// Adds `p` to the bitboards and the hash, or takes it out if it is already there
+    fn toggle_bitboards(&mut self, p: Piece) {
+        let square = bit(p.coord);
+        self.pieces[p.player as usize][p.kind as usize] ^= square;
+        self.occupancy[p.player as usize] ^= square;
+        self.hash ^= KEYS.piece(p.kind, p.player, p.coord);
+    }
+
     pub fn get_piece_by_coord(&self, coord: Coord) -> &Option<Piece> {
         &self.board[coord.row as usize][coord.col as usize]
     }
 
-    fn get_piece_by_index(&self, idx: usize, player: Player) -> Piece {
-        match player {
-            Player::Black => {
-                assert!(idx < self.black_pieces.len());
-                self.black_pieces[idx]
-            }
-            Player::White => {
-                assert!(idx < self.white_pieces.len());
-                self.white_pieces[idx]
-            }
-        }
+    /// Every piece of `player`, from a1 to h8
+    pub fn pieces(&self, player: Player) -> impl Iterator<Item = Piece> + '_ {
+        squares(self.occupancy[player as usize])
+            .map(|square| self.get_piece_by_coord(index_coord(square)).unwrap())
+    }
+
+    /// Squares occupied by the pieces of `player` of the given kind
+    pub fn bitboard(&self, kind: Kind, player: Player) -> Bitboard {
+        self.pieces[player as usize][kind as usize]
+    }
+
+    /// Squares occupied by any piece of `player`
+    pub fn occupancy(&self, player: Player) -> Bitboard {
+        self.occupancy[player as usize]
+    }
+
+    fn all_occupancy(&self) -> Bitboard {
+        self.occupancy[0] | self.occupancy[1]
     }
 
     fn is_square_occupied(&self, location: Coord) -> bool {
         self.get_piece_by_coord(location).is_some()
     }
-    fn player_at_square(&self, location: Coord) -> Option<Player> {
-        self.get_piece_by_coord(location).map(|piece| piece.player)
-    }
-    fn kind_at_square(&self, location: Coord) -> Option<Kind> {
+    pub(crate) fn kind_at_square(&self, location: Coord) -> Option<Kind> {
         self.get_piece_by_coord(location).map(|piece| piece.kind)
     }
 
     /// Function for debugging.
-    /// Checks if both the board and the piece vectors in the data structure are properly synced up.
+    /// Checks if both the board and the bitboards in the data structure are properly synced up,
+    /// and that the incrementally updated hash matches the position.
     pub fn check_everything(board: &Board) {
+        for player in [Player::White, Player::Black] {
+            let mut occupancy = 0;
+            for kind in [
+                Kind::Pawn,
+                Kind::Rook,
+                Kind::Knight,
+                Kind::Bishop,
+                Kind::Queen,
+                Kind::King,
+            ] {
+                for square in squares(board.bitboard(kind, player)) {
+                    let p = board.get_piece_by_coord(index_coord(square)).unwrap();
+                    assert!(p.kind == kind && p.player == player && p.coord == index_coord(square));
+                }
+                occupancy |= board.bitboard(kind, player);
+            }
+            assert_eq!(occupancy, board.occupancy(player));
+        }
         for row in 0..8 {
             for col in 0..8 {
-                if let Some(p) = board.get_piece_by_coord(Coord { row, col }) {
-                    match p.player {
-                        Player::Black => {
-                            // println!(
-                            //     "black[p.idx] = {:?}, *p = {:?}",
-                            //     board.black_pieces[p.idx], *p
-                            // );
-                            assert!(board.black_pieces[p.idx] == *p)
-                        }
-                        Player::White => {
-                            // println!(
-                            //     "white[p.idx] = {:?}, *p = {:?}",
-                            //     board.white_pieces[p.idx], *p
-                            // );
-                            assert!(board.white_pieces[p.idx] == *p)
-                        }
-                    }
-                };
+                let coord = Coord { row, col };
+                assert_eq!(
+                    board.is_square_occupied(coord),
+                    board.all_occupancy() & bit(coord) != 0
+                );
             }
         }
+        assert_eq!(board.hash, board.compute_zobrist_hash());
     }
 
-    #[auto_enum]
-    fn get_pseudo_legal_moves<'a>(&'a self, coord: Coord) -> impl Iterator<Item = Ply> + 'a {
-        #[auto_enum(Iterator)]
-        if let Some(piece_in_square) = self.get_piece_by_coord(coord) {
-            #[auto_enum(Iterator)]
-            match piece_in_square.kind {
-                Kind::Pawn => self
-                    .get_pawn_moves(coord)
-                    .chain(self.get_pawn_captures(coord))
-                    .chain(self.get_pawn_en_passant(coord).into_iter()),
-                Kind::Knight => self.get_knight_moves(coord),
-                Kind::Bishop => {
-                    self.get_queen_rook_bishop_moves(coord, Coord::LIST_DIAGONAL.into_iter())
-                }
+    /// Gives the legal moves of the piece on `coord`
+    pub fn get_legal_moves(&self, coord: Coord) -> impl Iterator<Item = Ply> + '_ {
+        self.get_all_moves().filter(move |ply| ply.origin == coord)
+    }
 
-                Kind::Rook => {
-                    self.get_queen_rook_bishop_moves(coord, Coord::LIST_CARDINAL.into_iter())
-                }
+    /// Gives the direction from the king to the piece pinning `p` against it, if any
+    pub fn is_piece_pinned(&self, p: Piece) -> Option<Coord> {
+        (p.kind != Kind::King && self.pinned_pieces(p.player) & bit(p.coord) != 0)
+            .then(|| Coord::find_dir_between_coords(p.coord, self.find_king(p.player)))
+    }
 
-                Kind::Queen => self
-                    .get_queen_rook_bishop_moves(coord, Coord::LIST_CARDINAL_DIAGONAL.into_iter()),
-                Kind::King => self.get_king_moves(coord).chain(self.get_castling_moves()),
-            }
-        } else {
-            std::iter::empty()
-        }
+    /// Pieces of `player` that can't leave the line between their king and an enemy slider
+    fn pinned_pieces(&self, player: Player) -> Bitboard {
+        let king = square_index(self.find_king(player));
+        let enemy = player.opponent();
+        let occupancy = self.all_occupancy();
+        let snipers = (rook_attacks(king, 0)
+            & (self.bitboard(Kind::Rook, enemy) | self.bitboard(Kind::Queen, enemy)))
+            | (bishop_attacks(king, 0)
+                & (self.bitboard(Kind::Bishop, enemy) | self.bitboard(Kind::Queen, enemy)));
+
+        squares(snipers)
+            .map(|sniper| between(king, sniper) & occupancy)
+            .filter(|blockers| blockers.count_ones() == 1)
+            .fold(0, |pinned, blockers| pinned | blockers)
+            & self.occupancy(player)
     }
 
-    #[auto_enum(Iterator)]
-    pub fn get_legal_moves<'a>(&'a self, coord: Coord) -> impl Iterator<Item = Ply> + 'a {
-        if let Some(piece_in_square) = self.get_piece_by_coord(coord) {
-            let king_loc = match self.turn {
-                Player::Black => self.black_king_loc,
-                Player::White => self.white_king_loc,
-            };
+    /// Pieces of `by_player` attacking `square`, with the board occupied by `occupancy`
+    fn attackers(&self, square: usize, by_player: Player, occupancy: Bitboard) -> Bitboard {
+        let queens = self.bitboard(Kind::Queen, by_player);
+        (pawn_attacks(square, by_player.opponent()) & self.bitboard(Kind::Pawn, by_player))
+            | (knight_attacks(square) & self.bitboard(Kind::Knight, by_player))
+            | (king_attacks(square) & self.bitboard(Kind::King, by_player))
+            | (bishop_attacks(square, occupancy)
+                & (self.bitboard(Kind::Bishop, by_player) | queens))
+            | (rook_attacks(square, occupancy) & (self.bitboard(Kind::Rook, by_player) | queens))
+    }
 
-            let mut iterator = self.square_attacked_by_pieces(king_loc, self.turn.opponent());
-            let first_checking_piece = iterator.next();
-            let second_checking_piece = iterator.next();
+    fn find_king(&self, player: Player) -> Coord {
+        match player {
+            Player::Black => self.black_king_loc,
+            Player::White => self.white_king_loc,
+        }
+    }
 
-            #[auto_enum(Iterator)]
-            let result = if second_checking_piece.is_some() {
-                #[auto_enum(Iterator)]
-                match piece_in_square.kind {
-                    Kind::King => self.get_king_moves(coord),
-                    _ => std::iter::empty(),
-                }
-            } else if let Some(first_checking_piece) = first_checking_piece {
-                #[auto_enum(Iterator)]
-                match piece_in_square.kind {
-                    Kind::King => self.get_king_moves(coord),
-
-                    _ => {
-                        if let Some(_) = self.is_piece_pinned(*piece_in_square) {
-                            return std::iter::empty();
-                        }
+    /// Whether the side to move is in check
+    pub fn is_in_check(&self) -> bool {
+        self.is_square_attacked(self.find_king(self.turn), self.turn.opponent())
+    }
 
-                        #[auto_enum(Iterator)]
-                        match first_checking_piece.kind {
-                            Kind::Pawn => {
-                                if let Some(en_passant_square) = self.en_passant_square {
-                                    if first_checking_piece.coord
-                                        == en_passant_square
-                                            + first_checking_piece.player.advancing_direction()
-                                        && piece_in_square.kind == Kind::Pawn
-                                    {
-                                        return self.get_pseudo_legal_moves(coord).filter(
-                                            move |ply| {
-                                                ply.destination == first_checking_piece.coord
-                                                    || ply.destination == en_passant_square
-                                            },
-                                        );
-                                    }
-                                }
-
-                                self.get_pseudo_legal_moves(coord).filter(move |ply| {
-                                    ply.destination == first_checking_piece.coord
-                                })
-                            }
-                            Kind::Knight => self
-                                .get_pseudo_legal_moves(coord)
-                                .filter(move |ply| ply.destination == first_checking_piece.coord),
-
-                            Kind::Rook | Kind::Bishop | Kind::Queen => {
-                                let dir = Coord::find_dir_between_coords(
-                                    first_checking_piece.coord,
-                                    king_loc,
-                                );
-
-                                let pos = king_loc + dir;
-                                let row_min = pos.row.min(first_checking_piece.coord.row);
-                                let col_min = pos.col.min(first_checking_piece.coord.col);
-                                let row_max = pos.row.max(first_checking_piece.coord.row);
-                                let col_max = pos.col.max(first_checking_piece.coord.col);
-
-                                self.get_pseudo_legal_moves(coord).filter(move |ply| {
-                                    ply.destination.row >= row_min
-                                        && ply.destination.col >= col_min
-                                        && ply.destination.row <= row_max
-                                        && ply.destination.col <= col_max
-                                        && if dir.row.abs() == dir.col.abs() {
-                                            (ply.destination.row - king_loc.row).abs()
-                                                == (ply.destination.col - king_loc.col).abs()
-                                        } else {
-                                            true
-                                        }
-                                })
-                            }
-                            Kind::King => panic!("King cannot be the checking piece"),
-                        }
-                    }
-                }
-            } else {
-                #[auto_enum(Iterator)]
-                if let Some(pin_direction) = self.is_piece_pinned(*piece_in_square) {
-                    #[auto_enum(Iterator)]
-                    match piece_in_square.kind {
-                        Kind::Pawn => self
-                            .get_pawn_moves(coord)
-                            .chain(self.get_pawn_captures(coord))
-                            .chain(self.get_pawn_en_passant(coord))
-                            // TODO: refactor using pseudo_legal_moves
-                            .filter(move |ply| {
-                                ply.destination == piece_in_square.coord + pin_direction
-                                    || ply.destination == piece_in_square.coord + 2 * pin_direction
-                            }),
-
-                        Kind::Knight => std::iter::empty(),
-                        Kind::Rook => self.get_queen_rook_bishop_moves(
-                            coord,
-                            Coord::LIST_CARDINAL.into_iter().filter(move |&dir| {
-                                dir == pin_direction || dir == -1 * pin_direction
-                            }),
-                        ),
-                        Kind::Bishop => self.get_queen_rook_bishop_moves(
-                            coord,
-                            Coord::LIST_DIAGONAL.into_iter().filter(move |&dir| {
-                                dir == pin_direction || dir == -1 * pin_direction
-                            }),
-                        ),
-                        Kind::Queen => self.get_queen_rook_bishop_moves(
-                            coord,
-                            Coord::LIST_CARDINAL_DIAGONAL
-                                .into_iter()
-                                .filter(move |&dir| {
-                                    dir == pin_direction || dir == -1 * pin_direction
-                                }),
-                        ),
-
-                        Kind::King => unreachable!(),
-                    }
-                } else {
-                    if piece_in_square.kind == Kind::Pawn
-                        && self.is_pawn_enpassant_pinned(*piece_in_square)
-                    {
-                        return self
-                            .get_pawn_moves(coord)
-                            .chain(self.get_pawn_captures(coord));
-                    }
+    // Gives all posible moves in a position
+    pub fn get_all_moves(&self) -> impl Iterator<Item = Ply> + '_ {
+        self.legal_moves().into_iter()
+    }
 
-                    self.get_pseudo_legal_moves(coord)
-                }
-            };
+    /// Every legal move of the position, in a list that lives on the stack
+    pub fn legal_moves(&self) -> MoveList {
+        self.filtered_moves(MoveFilter::All)
+    }
 
-            result
-        } else {
-            std::iter::empty()
+    /// The legal moves of the kind selected by `filter`, in a list that lives on the stack
+    pub fn filtered_moves(&self, filter: MoveFilter) -> MoveList {
+        let mut moves = MoveList::new();
+        self.generate_moves(&mut moves, filter);
+        if filter == MoveFilter::Checks {
+            moves.retain(|ply| self.gives_check(ply));
         }
+        moves
     }
 
-    pub fn is_piece_pinned(&self, p: Piece) -> Option<Coord> {
-        if p.kind == Kind::King {
-            return None;
-        }
+    /// Generates the legal moves selected by `filter` in a single pass over the bitboards.
+    /// Checks and pins are worked out up front, so no move has to be played to find out
+    /// whether it leaves the king in check. Captures and quiet moves are told apart by the
+    /// squares they may land on, so generating one kind skips the other entirely
+    fn generate_moves(&self, moves: &mut MoveList, filter: MoveFilter) {
+        let player = self.turn;
+        let enemy = player.opponent();
+        let own = self.occupancy(player);
+        let occupancy = self.all_occupancy();
+        let king = square_index(self.find_king(player));
+        let promotion_ranks = RANK_1 | RANK_8;
+
+        // Squares pieces other than pawns may land on
+        let filter_targets = match filter {
+            MoveFilter::All | MoveFilter::Checks => !own,
+            MoveFilter::Captures => self.occupancy(enemy),
+            MoveFilter::Quiet => !occupancy,
+        };
 
-        let king_loc = match p.player {
-            Player::Black => self.black_king_loc,
-            Player::White => self.white_king_loc,
+        let checkers = self.attackers(king, enemy, occupancy);
+        let push = |moves: &mut MoveList, origin: usize, targets: Bitboard| {
+            moves.extend(squares(targets).map(|destination| Ply {
+                origin: index_coord(origin),
+                destination: index_coord(destination),
+                promotion: None,
+            }))
         };
 
-        let dir = Coord::find_dir_between_coords(p.coord, king_loc);
+        // The king can't step along the ray of a slider checking it, so look through it
+        let king_targets = squares(king_attacks(king) & filter_targets)
+            .filter(|&destination| self.attackers(destination, enemy, occupancy ^ (1 << king)) == 0)
+            .fold(0, |targets, destination| targets | 1 << destination);
+        push(moves, king, king_targets);
 
-        for i in 1..8 {
-            let pos = king_loc + i * dir;
-            if !pos.is_valid() || (self.is_square_occupied(pos) && pos != p.coord) {
-                return None;
-            };
-            if pos == p.coord {
-                for j in 1..8 {
-                    let pos_after_piece = p.coord + j * dir;
-                    if !pos_after_piece.is_valid() {
-                        return None;
-                    }
-
-                    if let Some(piece) = self.get_piece_by_coord(pos_after_piece) {
-                        let pinning_piece = {
-                            if dir.row == dir.col || dir.row == -1 * dir.col {
-                                Kind::Bishop
-                            } else {
-                                Kind::Rook
-                            }
-                        };
-
-                        if piece.player == p.player
-                            || (piece.kind != pinning_piece && piece.kind != Kind::Queen)
-                        {
-                            return None;
-                        }
+        if checkers.count_ones() > 1 {
+            return;
+        }
 
-                        if piece.kind == pinning_piece || piece.kind == Kind::Queen {
-                            return Some(dir);
-                        }
-                    }
-                }
+        // With a single checker, other pieces must capture it or block its ray
+        let target = match checkers {
+            0 => !own,
+            _ => checkers | between(king, checkers.trailing_zeros() as usize),
+        };
+        let pinned = self.pinned_pieces(player);
+        let allowed = |origin: usize| {
+            if pinned & 1 << origin != 0 {
+                target & line(king, origin)
+            } else {
+                target
             }
-        }
-        None
-    }
+        };
+        let piece_allowed = |origin: usize| allowed(origin) & filter_targets;
 
-    fn is_pawn_enpassant_pinned(&self, p: Piece) -> bool {
-        if p.kind != Kind::Pawn {
-            return false;
+        for origin in squares(self.bitboard(Kind::Knight, player) & !pinned) {
+            push(
+                moves,
+                origin,
+                knight_attacks(origin) & piece_allowed(origin),
+            );
+        }
+        for origin in squares(self.bitboard(Kind::Bishop, player)) {
+            push(
+                moves,
+                origin,
+                bishop_attacks(origin, occupancy) & piece_allowed(origin),
+            );
+        }
+        for origin in squares(self.bitboard(Kind::Rook, player)) {
+            push(
+                moves,
+                origin,
+                rook_attacks(origin, occupancy) & piece_allowed(origin),
+            );
+        }
+        for origin in squares(self.bitboard(Kind::Queen, player)) {
+            push(
+                moves,
+                origin,
+                queen_attacks(origin, occupancy) & piece_allowed(origin),
+            );
         }
 
-        if let Some(en_passant_square) = self.en_passant_square {
-            let king_loc = match p.player {
-                Player::Black => self.black_king_loc,
-                Player::White => self.white_king_loc,
-            };
+        let forward = player.advancing_direction();
+        for origin in squares(self.bitboard(Kind::Pawn, player)) {
+            let coord = index_coord(origin);
+            let captures = pawn_attacks(origin, player) & self.occupancy(enemy);
+
+            let mut pushes = 0;
+            let single = coord + forward;
+            if !self.is_square_occupied(single) {
+                pushes |= bit(single);
+                let double = single + forward;
+                if coord.row == player.pawn_row() && !self.is_square_occupied(double) {
+                    pushes |= bit(double);
+                }
+            }
 
-            let dir = Coord::find_dir_between_coords(p.coord, king_loc);
+            // Promotions count as captures, even when the pawn just pushes
+            let targets = match filter {
+                MoveFilter::All | MoveFilter::Checks => captures | pushes,
+                MoveFilter::Captures => captures | (pushes & promotion_ranks),
+                MoveFilter::Quiet => pushes & !promotion_ranks,
+            };
 
-            for i in 1..8 {
-                let pos = king_loc + i * dir;
-                if pos == en_passant_square + p.player.opponent().advancing_direction() {
-                    continue;
+            for destination in squares(targets & allowed(origin)).map(index_coord) {
+                if destination.row == 0 || destination.row == 7 {
+                    moves.extend(Kind::PROMOTIONS.iter().map(|&promo| Ply {
+                        origin: coord,
+                        destination,
+                        promotion: Some(promo),
+                    }));
+                } else {
+                    moves.push(Ply {
+                        origin: coord,
+                        destination,
+                        promotion: None,
+                    });
                 }
-                if !pos.is_valid() || (self.is_square_occupied(pos) && pos != p.coord) {
-                    return false;
-                };
-                if pos == p.coord {
-                    for j in 1..8 {
-                        let pos_after_piece = p.coord + j * dir;
-                        if pos_after_piece
-                            == en_passant_square + p.player.opponent().advancing_direction()
-                        {
-                            continue;
-                        }
-                        if !pos_after_piece.is_valid() {
-                            return false;
-                        }
+            }
 
-                        if let Some(piece) = self.get_piece_by_coord(pos_after_piece) {
-                            let pinning_piece = {
-                                if dir.row == dir.col || dir.row == -1 * dir.col {
-                                    Kind::Bishop
-                                } else {
-                                    Kind::Rook
-                                }
-                            };
-
-                            if piece.player == p.player
-                                || (piece.kind != pinning_piece && piece.kind != Kind::Queen)
-                            {
-                                return false;
-                            }
-
-                            if piece.kind == pinning_piece || piece.kind == Kind::Queen {
-                                return true;
-                            }
-                        }
-                    }
+            if let Some(en_passant_square) = self
+                .en_passant_square
+                .filter(|_| filter != MoveFilter::Quiet)
+            {
+                if pawn_attacks(origin, player) & bit(en_passant_square) != 0
+                    && self.is_en_passant_legal(origin, en_passant_square, checkers)
+                {
+                    moves.push(Ply {
+                        origin: coord,
+                        destination: en_passant_square,
+                        promotion: None,
+                    });
                 }
             }
         }
-        false
-    }
 
-    fn find_king(&self, player: Player) -> Coord {
-        match player {
-            Player::Black => self.black_king_loc,
-            Player::White => self.white_king_loc,
+        if checkers == 0 && filter != MoveFilter::Captures {
+            moves.extend(self.get_castling_moves());
         }
     }
 
-    // Gives all posible moves in a position
-    pub fn get_all_moves<'a>(&'a self) -> impl Iterator<Item = Ply> + 'a {
-        match self.turn {
-            Player::Black => &self.black_pieces,
-            Player::White => &self.white_pieces,
-        }
-        .into_iter()
-        .flat_map(|p| self.get_legal_moves(p.coord))
+    /// En passant takes two pawns off the same rank at once, so the usual pin logic doesn't
+    /// cover it. Instead, look at the king after the capture
+    fn is_en_passant_legal(
+        &self,
+        origin: usize,
+        en_passant_square: Coord,
+        checkers: Bitboard,
+    ) -> bool {
+        let player = self.turn;
+        let enemy = player.opponent();
+        let captured = bit(en_passant_square - player.advancing_direction());
+        let king = square_index(self.find_king(player));
+        let occupancy = (self.all_occupancy() ^ (1 << origin) ^ captured) | bit(en_passant_square);
+
+        // A knight or pawn giving check can only be dealt with by capturing it
+        let stepping_checkers = checkers
+            & (self.bitboard(Kind::Knight, enemy) | self.bitboard(Kind::Pawn, enemy))
+            & !captured;
+        let queens = self.bitboard(Kind::Queen, enemy);
+        let sliders = (bishop_attacks(king, occupancy)
+            & (self.bitboard(Kind::Bishop, enemy) | queens))
+            | (rook_attacks(king, occupancy) & (self.bitboard(Kind::Rook, enemy) | queens));
+
+        stepping_checkers == 0 && sliders == 0
     }
 
-    pub fn get_all_moves_par<'a>(&'a self) -> impl ParallelIterator<Item = Ply> + 'a {
-        match self.turn {
-            Player::Black => &self.black_pieces,
-            Player::White => &self.white_pieces,
-        }
-        .into_par_iter()
-        .flat_map_iter(|p| self.get_legal_moves(p.coord))
+    /// Gives the legal moves of the kind selected by `filter`
+    pub fn get_moves(&self, filter: MoveFilter) -> impl Iterator<Item = Ply> + '_ {
+        self.filtered_moves(filter).into_iter()
     }
 
-    fn get_king_moves<'a>(&'a self, origin: Coord) -> impl Iterator<Item = Ply> + 'a {
-        let player = self.player_at_square(origin).unwrap();
+    /// Gives all legal captures (including en passant) and promotions in a position
+    pub fn get_all_captures(&self) -> impl Iterator<Item = Ply> + '_ {
+        self.get_moves(MoveFilter::Captures)
+    }
 
-        Coord::LIST_CARDINAL_DIAGONAL
-            .iter()
-            .map(move |&delta| origin + delta)
-            .filter(move |&pos| {
-                pos.is_valid()
-                    && self.player_at_square(pos) != Some(player)
-                    && !self.is_square_attacked(pos, player.opponent())
-            })
-            .map(move |pos| Ply {
-                origin,
-                destination: pos,
-                promotion: None,
-            })
+    /// Gives all legal moves that neither capture nor promote
+    pub fn get_quiet_moves(&self) -> impl Iterator<Item = Ply> + '_ {
+        self.get_moves(MoveFilter::Quiet)
     }
 
-    fn get_knight_moves<'a>(&'a self, origin: Coord) -> impl Iterator<Item = Ply> + 'a {
-        let player = self.player_at_square(origin).unwrap();
+    /// Gives all legal moves that put the opponent in check
+    pub fn get_checks(&self) -> impl Iterator<Item = Ply> + '_ {
+        self.get_moves(MoveFilter::Checks)
+    }
 
-        Coord::LIST_KNIGHT
-            .iter()
-            .map(move |&delta| origin + delta)
-            .filter(move |&pos| pos.is_valid() && self.player_at_square(pos) != Some(player))
-            .map(move |pos| Ply {
-                origin,
-                destination: pos,
-                promotion: None,
-            })
+    /// Whether `ply` puts the opponent in check, either with the moved piece or by moving
+    /// out of the way of a slider aimed at the enemy king
+    pub fn gives_check(&self, ply: Ply) -> bool {
+        let player = self.turn;
+        let enemy_king = square_index(self.find_king(player.opponent()));
+        let piece = self.get_piece_by_coord(ply.origin).unwrap();
+
+        // Squares the moving pieces leave, the occupancy once they have moved, and the kind
+        // and square of the piece that may give a direct check
+        let (moved, mut occupancy, kind, destination) = match self.castling_move(ply) {
+            Some(castling) => {
+                let moved = bit(ply.origin) | bit(castling.rook_origin);
+                let landed = bit(castling.king_destination) | bit(castling.rook_destination);
+                (
+                    moved,
+                    self.all_occupancy() & !moved | landed,
+                    Kind::Rook,
+                    square_index(castling.rook_destination),
+                )
+            }
+            None => (
+                bit(ply.origin),
+                self.all_occupancy() & !bit(ply.origin) | bit(ply.destination),
+                ply.promotion.unwrap_or(piece.kind),
+                square_index(ply.destination),
+            ),
+        };
+
+        // A pawn taken en passant may have been blocking a slider too
+        if piece.kind == Kind::Pawn
+            && ply.origin.col != ply.destination.col
+            && !self.is_square_occupied(ply.destination)
+        {
+            occupancy ^= bit(ply.destination - player.advancing_direction());
+        }
+
+        let direct = match kind {
+            Kind::Pawn => pawn_attacks(destination, player),
+            Kind::Knight => knight_attacks(destination),
+            Kind::Bishop => bishop_attacks(destination, occupancy),
+            Kind::Rook => rook_attacks(destination, occupancy),
+            Kind::Queen => queen_attacks(destination, occupancy),
+            Kind::King => 0,
+        };
+        let discovered = self.attackers(enemy_king, player, occupancy) & !moved;
+
+        direct & 1 << enemy_king != 0 || discovered != 0
     }
 
-    fn get_queen_rook_bishop_moves<'a>(
-        &'a self,
-        origin: Coord,
-        directions: impl Iterator<Item = Coord> + 'a,
-    ) -> impl Iterator<Item = Ply> + 'a {
-        let player = self.player_at_square(origin).unwrap();
-
-        directions
-            .map(move |dir| {
-                (1..)
-                    .map(move |i| origin + dir * i)
-                    .take_while(|&c| c.is_valid())
-                    .take_while(move |&c| self.player_at_square(c) != Some(player))
-                    .take_while(move |&c| self.player_at_square(c - dir) != Some(player.opponent()))
-                    .map(move |c| Ply {
-                        origin,
-                        destination: c,
-                        promotion: None,
-                    })
-            })
-            .flatten()
+    /// Whether `ply` captures a piece, including en passant
+    pub fn is_capture(&self, ply: Ply) -> bool {
+        self.occupancy(self.turn.opponent()) & bit(ply.destination) != 0
+            || (self.kind_at_square(ply.origin) == Some(Kind::Pawn)
+                && ply.origin.col != ply.destination.col)
+    }
+
+    pub fn get_all_moves_par(&self) -> impl ParallelIterator<Item = Ply> + '_ {
+        self.legal_moves().to_vec().into_par_iter()
     }
 
+    /// Returns the position after `ply`, leaving `self` untouched
     pub fn make_move(&self, ply: Ply) -> Board {
         let mut new_game_state = self.clone();
+        new_game_state.make_move_mut(ply);
+        new_game_state
+    }
 
+    /// Plays `ply` in place. The returned record lets `unmake_move` take it back, which is
+    /// cheaper than copying the board when walking a tree of moves
+    pub fn make_move_mut(&mut self, ply: Ply) -> Undo {
         let piece = self.get_piece_by_coord(ply.origin).unwrap();
         let dir = piece.player.advancing_direction();
+        let castling = self.castling_move(ply);
+        let is_en_passant = piece.kind == Kind::Pawn
+            && ply.origin.col != ply.destination.col
+            && !self.is_square_occupied(ply.destination);
+        let captured_coord = if is_en_passant {
+            ply.destination - dir
+        } else {
+            ply.destination
+        };
+
+        let undo = Undo {
+            ply,
+            // In Chess960 the castling king lands on its own rook, which isn't a capture
+            captured: self
+                .get_piece_by_coord(captured_coord)
+                .filter(|p| p.player != piece.player),
+            castling_rights: [
+                self.white_can_oo,
+                self.white_can_ooo,
+                self.black_can_oo,
+                self.black_can_ooo,
+            ],
+            en_passant_square: self.en_passant_square,
+            half_move_clock: self.half_move_clock,
+            hash: self.hash,
+            castling,
+        };
+        self.hash ^= self.castling_hash() ^ self.en_passant_hash();
 
         // Detect if move was capture or pawn push and update half_move clock
-        if piece.kind == Kind::Pawn || self.is_square_occupied(ply.destination) {
-            new_game_state.half_move_clock = 0;
+        if piece.kind == Kind::Pawn || undo.captured.is_some() {
+            self.half_move_clock = 0;
         } else {
-            new_game_state.half_move_clock += 1;
+            self.half_move_clock += 1;
         }
 
-        // Detect if move was en passant and remove the captured pawn
-        if piece.kind == Kind::Pawn
-            && ply.origin.col != ply.destination.col
-            && self.get_piece_by_coord(ply.destination).is_none()
-        {
-            new_game_state.remove_piece_from_occupied_square(ply.destination - dir);
+        // Remove the pawn captured en passant
+        if is_en_passant {
+            self.remove_piece_from_occupied_square(captured_coord);
         }
 
         // Update en_passant_square
-        new_game_state.en_passant_square =
+        self.en_passant_square =
             if piece.kind == Kind::Pawn && ply.destination == ply.origin + 2 * dir {
                 Some(ply.origin + dir)
             } else {
                 None
             };
 
-        // Detect if move was castle and move rook to correct position
-        if piece.kind == Kind::King {
-            if ply.destination == ply.origin + 2 * Coord::R {
-                new_game_state
-                    .move_piece_by_coord(ply.destination + Coord::R, ply.destination + Coord::L);
-            }
-
-            if ply.destination == ply.origin + 2 * Coord::L {
-                new_game_state.move_piece_by_coord(
-                    ply.destination + 2 * Coord::L,
-                    ply.destination + Coord::R,
-                );
-            }
-        }
-
         // Update castle permissions
         if piece.kind == Kind::King {
             match piece.player {
                 Player::Black => {
-                    new_game_state.black_can_oo = false;
-                    new_game_state.black_can_ooo = false;
+                    self.black_can_oo = false;
+                    self.black_can_ooo = false;
                 }
                 Player::White => {
-                    new_game_state.white_can_oo = false;
-                    new_game_state.white_can_ooo = false;
+                    self.white_can_oo = false;
+                    self.white_can_ooo = false;
                 }
             }
         }
 
-        [
-            (
-                &mut new_game_state.white_can_ooo,
-                Player::White.home_row(),
-                0,
-            ),
-            (
-                &mut new_game_state.white_can_oo,
-                Player::White.home_row(),
-                7,
-            ),
-            (
-                &mut new_game_state.black_can_ooo,
-                Player::Black.home_row(),
-                0,
-            ),
-            (
-                &mut new_game_state.black_can_oo,
-                Player::Black.home_row(),
-                7,
-            ),
-        ]
-        .into_iter()
-        .for_each(|(castle_perm, row, col)| {
-            let coord = Coord { row, col };
-            if ply.origin == coord || ply.destination == coord {
-                *castle_perm = false;
+        // A rook moving away from its home square or captured on it can't castle anymore
+        for player in [Player::White, Player::Black] {
+            for side in 0..2 {
+                let coord = Coord {
+                    row: player.home_row(),
+                    col: self.rook_files[player as usize][side],
+                };
+                if ply.origin == coord || ply.destination == coord {
+                    *self.castling_right_mut(player, side) = false;
+                }
             }
-        });
-
-        // Set piece (including promotions) on new square
-        if let Some(promo) = ply.promotion {
-            new_game_state.promote_piece(piece, promo);
         }
-        new_game_state.move_piece_by_coord(ply.origin, ply.destination);
-
-        // Change turn and return new game state
-        new_game_state.turn = new_game_state.turn.opponent();
-        new_game_state
-    }
 
-    #[auto_enum(Iterator)]
-    fn get_pawn_moves<'a>(&'a self, origin: Coord) -> impl Iterator<Item = Ply> + 'a {
-        let player = self.player_at_square(origin).unwrap();
-        let dir = player.advancing_direction();
-        let destination = origin + dir;
-
-        if self.is_square_occupied(destination) {
-            std::iter::empty()
+        if let Some(castling) = castling {
+            // Take the rook off first, as the king may land where it stood
+            let rook = self.get_piece_by_coord(castling.rook_origin).unwrap();
+            self.remove_piece_from_occupied_square(castling.rook_origin);
+            self.move_piece_by_coord(ply.origin, castling.king_destination);
+            self.add_piece_to_empty_square(Piece {
+                coord: castling.rook_destination,
+                ..rook
+            });
         } else {
-            #[auto_enum(Iterator)]
-            let iter = if destination.row == 0 || destination.row == 7 {
-                Kind::PROMOTIONS.iter().map(move |&promo| Ply {
-                    origin,
-                    destination,
-                    promotion: Some(promo),
-                })
-            } else {
-                std::iter::once(Ply {
-                    origin,
-                    destination,
-                    promotion: None,
-                })
-            };
-
-            #[auto_enum(Iterator)]
-            let iter =
-                if origin.row == player.pawn_row() && !self.is_square_occupied(destination + dir) {
-                    iter.chain(std::iter::once(Ply {
-                        origin,
-                        destination: destination + dir,
-                        promotion: None,
-                    }))
-                } else {
-                    iter
-                };
+            // Set piece (including promotions) on new square
+            if let Some(promo) = ply.promotion {
+                self.promote_piece(piece, promo);
+            }
+            self.move_piece_by_coord(ply.origin, ply.destination);
+        }
 
-            iter
+        // Advance the fullmove number after Black's move
+        if piece.player == Player::Black {
+            self.full_move_number += 1;
         }
-    }
 
-    #[auto_enum]
-    fn get_pawn_captures<'a>(&'a self, origin: Coord) -> impl Iterator<Item = Ply> + 'a {
-        let player = self.player_at_square(origin).unwrap();
+        // Change turn
+        self.turn = self.turn.opponent();
+        self.hash ^= KEYS.black_to_move ^ self.castling_hash() ^ self.en_passant_hash();
 
-        [Coord::L, Coord::R]
-            .iter()
-            .map(move |&c| origin + c + player.advancing_direction())
-            .filter(move |&pos| {
-                pos.is_valid() && self.player_at_square(pos) == Some(player.opponent())
-            })
-            .map(move |pos| {
-                #[auto_enum(Iterator)]
-                if pos.row == 7 || pos.row == 0 {
-                    Kind::PROMOTIONS.iter().map(move |&promo| Ply {
-                        origin,
-                        destination: pos,
-                        promotion: Some(promo),
-                    })
-                } else {
-                    std::iter::once(Ply {
-                        origin,
-                        destination: pos,
-                        promotion: None,
-                    })
-                }
-            })
-            .flatten()
+        undo
     }
 
-    fn get_pawn_en_passant(&self, origin: Coord) -> Option<Ply> {
-        let player = self.player_at_square(origin).unwrap();
-
-        let Some(en_passant_square) = self.en_passant_square else {
-            return None;
-        };
-
-        if (origin + player.advancing_direction()).row != en_passant_square.row {
-            return None;
+    /// Takes back the move `undo` was returned for by `make_move_mut`.
+    /// It has to be the last move played on this board
+    pub fn unmake_move(&mut self, undo: Undo) {
+        let ply = undo.ply;
+        self.turn = self.turn.opponent();
+        if self.turn == Player::Black {
+            self.full_move_number -= 1;
         }
 
-        if (origin.col + 1 == en_passant_square.col) || (origin.col - 1 == en_passant_square.col) {
-            Some(Ply {
-                origin,
-                destination: en_passant_square,
-                promotion: None,
-            })
+        if let Some(castling) = undo.castling {
+            let rook = self.get_piece_by_coord(castling.rook_destination).unwrap();
+            self.remove_piece_from_occupied_square(castling.rook_destination);
+            self.move_piece_by_coord(castling.king_destination, ply.origin);
+            self.add_piece_to_empty_square(Piece {
+                coord: castling.rook_origin,
+                ..rook
+            });
         } else {
-            None
+            self.move_piece_by_coord(ply.destination, ply.origin);
+            let piece = self.get_piece_by_coord(ply.origin).unwrap();
+            if ply.promotion.is_some() {
+                self.promote_piece(piece, Kind::Pawn);
+            }
         }
-    }
 
-    pub fn is_square_attacked(&self, origin: Coord, by_player: Player) -> bool {
-        let king_loc = match by_player {
-            Player::Black => self.white_king_loc,
-            Player::White => self.black_king_loc,
-        };
+        if let Some(captured) = undo.captured {
+            self.add_piece_to_empty_square(captured);
+        }
 
-        Coord::LIST_CARDINAL
-            .iter()
-            .map(|c| (c, Kind::Rook))
-            .chain(Coord::LIST_DIAGONAL.iter().map(|c| (c, Kind::Bishop)))
-            .any(|(&dir, kind)| {
-                (1..)
-                    .map(move |i| origin + dir * i)
-                    .take_while(|&c| c.is_valid())
-                    .take_while(|&c| {
-                        self.player_at_square(c) != Some(by_player.opponent()) || c == king_loc
-                    })
-                    .take_while(move |&c| {
-                        self.player_at_square(c - dir) != Some(by_player) || c - dir == origin
-                    })
-                    .filter(|&c| self.player_at_square(c) == Some(by_player))
-                    .any(|c| {
-                        self.kind_at_square(c) == Some(kind)
-                            || self.kind_at_square(c) == Some(Kind::Queen)
-                    })
-            })
-            || [Coord::L, Coord::R]
-                .iter()
-                .map(|&c| origin + c - by_player.advancing_direction())
-                .filter(|&c| c.is_valid())
-                .map(|c| self.get_piece_by_coord(c))
-                .flatten()
-                .any(|p| p.kind == Kind::Pawn && p.player == by_player)
-            || [
-                (Coord::LIST_KNIGHT, Kind::Knight),
-                (Coord::LIST_CARDINAL_DIAGONAL, Kind::King),
-            ]
-            .into_iter()
-            .any(|(coords, piece)| {
-                coords
-                    .iter()
-                    .map(|&c| origin + c)
-                    .filter(|&c| c.is_valid())
-                    .map(|c| self.get_piece_by_coord(c))
-                    .flatten()
-                    .any(|p| p.kind == piece && p.player == by_player)
-            })
+        [
+            self.white_can_oo,
+            self.white_can_ooo,
+            self.black_can_oo,
+            self.black_can_ooo,
+        ] = undo.castling_rights;
+        self.en_passant_square = undo.en_passant_square;
+        self.half_move_clock = undo.half_move_clock;
+        // The piece helpers updated the hash as they went, but restoring it is simpler
+        self.hash = undo.hash;
     }
 
-    fn square_attacked_by_pieces<'a>(
-        &'a self,
-        origin: Coord,
-        by_player: Player,
-    ) -> impl Iterator<Item = Piece> + 'a {
-        let iter1 = Coord::LIST_CARDINAL
-            .iter()
-            .map(|c| (c, Kind::Rook))
-            .chain(Coord::LIST_DIAGONAL.iter().map(|c| (c, Kind::Bishop)))
-            .flat_map(move |(&dir, piece)| {
-                (1..)
-                    .map(move |i| origin + dir * i)
-                    .take_while(|&c| c.is_valid())
-                    .take_while(move |&c| self.player_at_square(c) != Some(by_player.opponent()))
-                    .take_while(move |&c| self.player_at_square(c - dir) != Some(by_player))
-                    .filter(move |&c| self.player_at_square(c) == Some(by_player))
-                    .filter(move |&c| {
-                        self.kind_at_square(c) == Some(piece)
-                            || self.kind_at_square(c) == Some(Kind::Queen)
-                    })
-                    .map(|c| self.get_piece_by_coord(c).unwrap())
-            });
+    /// Where the king and rook go if `ply` castles. Castling is written as the king moving
+    /// two squares in standard chess, and as the king taking its own rook in Chess960
+    pub(crate) fn castling_move(&self, ply: Ply) -> Option<Castling> {
+        let king = self
+            .get_piece_by_coord(ply.origin)
+            .filter(|p| p.kind == Kind::King)?;
+        let row = ply.origin.row;
+        let kingside = ply.destination.col > ply.origin.col;
+
+        let rook_origin = if self
+            .get_piece_by_coord(ply.destination)
+            .is_some_and(|p| p.kind == Kind::Rook && p.player == king.player)
+        {
+            ply.destination
+        } else if !self.chess960
+            && ply.destination.row == row
+            && (ply.destination.col - ply.origin.col).abs() == 2
+        {
+            Coord {
+                row,
+                col: self.rook_files[king.player as usize][usize::from(!kingside)],
+            }
+        } else {
+            return None;
+        };
 
-        let iter2 = [Coord::L, Coord::R]
-            .iter()
-            .map(move |&c| origin + c - by_player.advancing_direction())
-            .filter(|&c| c.is_valid())
-            .map(|c| self.get_piece_by_coord(c))
-            .flatten()
-            .filter(move |p| p.kind == Kind::Pawn && p.player == by_player)
-            .cloned();
-
-        let iter3 = [
-            (&Coord::LIST_KNIGHT, Kind::Knight),
-            (&Coord::LIST_CARDINAL_DIAGONAL, Kind::King),
-        ]
-        .into_iter()
-        .flat_map(move |(coords, piece)| {
-            coords
-                .iter()
-                .map(move |&c| origin + c)
-                .filter(|&c| c.is_valid())
-                .map(|c| self.get_piece_by_coord(c))
-                .flatten()
-                .filter(move |p| p.kind == piece && p.player == by_player)
-                .cloned()
-        });
+        let (king_col, rook_col) = if kingside { (6, 5) } else { (2, 3) };
+        Some(Castling {
+            kingside,
+            king_destination: Coord { row, col: king_col },
+            rook_origin,
+            rook_destination: Coord { row, col: rook_col },
+        })
+    }
 
-        iter1.chain(iter2).chain(iter3)
+    pub fn is_square_attacked(&self, origin: Coord, by_player: Player) -> bool {
+        self.attackers(square_index(origin), by_player, self.all_occupancy()) != 0
     }
 
-    fn get_castling_moves<'a>(&'a self) -> impl Iterator<Item = Ply> + 'a {
+    /// Castling moves of the side to move, which must not be in check.
+    /// The king always ends on the g- or c-file with the rook next to it, wherever they
+    /// started, so every square on their way must be empty and none the king crosses
+    /// attacked
+    fn get_castling_moves(&self) -> impl Iterator<Item = Ply> + '_ {
         let player = self.turn;
         let row = player.home_row();
-        let permissions = match player {
-            Player::White => (self.white_can_oo, self.white_can_ooo),
-            Player::Black => (self.black_can_oo, self.black_can_ooo),
-        };
+        let king = self.find_king(player);
+        let rights = self.castling_rights(player);
 
-        (0..2).filter_map(move |i| match i {
-            0 if permissions.0
-                && !self.is_square_attacked(Coord { row, col: 4 }, player.opponent())
-                && !self.is_square_attacked(Coord { row, col: 5 }, player.opponent())
-                && !self.is_square_attacked(Coord { row, col: 6 }, player.opponent())
-                && !self.is_square_occupied(Coord { row, col: 5 })
-                && !self.is_square_occupied(Coord { row, col: 6 }) =>
-            {
-                Some(Ply {
-                    origin: Coord { row, col: 4 },
-                    destination: Coord { row, col: 6 },
-                    promotion: None,
-                })
-            }
+        [(6, 5), (2, 3)]
+            .into_iter()
+            .enumerate()
+            .filter(move |&(side, _)| rights[side])
+            .filter_map(move |(side, (king_col, rook_col))| {
+                let rook = Coord {
+                    row,
+                    col: self.rook_files[player as usize][side],
+                };
+                let (king, rook) = (square_index(king), square_index(rook));
+                let king_destination = square_index(Coord { row, col: king_col });
+                let rook_destination = square_index(Coord { row, col: rook_col });
+
+                // The king and rook may jump over each other
+                let occupancy = self.all_occupancy() ^ 1 << king ^ 1 << rook;
+                let king_path = between(king, king_destination) | 1 << king_destination;
+                let rook_path = between(rook, rook_destination) | 1 << rook_destination;
+
+                if occupancy & (king_path | rook_path) != 0
+                    || squares(king_path)
+                        .any(|square| self.attackers(square, player.opponent(), occupancy) != 0)
+                {
+                    return None;
+                }
 
-            1 if permissions.1
-                && !self.is_square_attacked(Coord { row, col: 4 }, player.opponent())
-                && !self.is_square_attacked(Coord { row, col: 2 }, player.opponent())
-                && !self.is_square_attacked(Coord { row, col: 3 }, player.opponent())
-                && !self.is_square_occupied(Coord { row, col: 1 })
-                && !self.is_square_occupied(Coord { row, col: 2 })
-                && !self.is_square_occupied(Coord { row, col: 3 }) =>
-            {
                 Some(Ply {
-                    origin: Coord { row, col: 4 },
-                    destination: Coord { row, col: 2 },
+                    origin: index_coord(king),
+                    destination: index_coord(if self.chess960 {
+                        rook
+                    } else {
+                        king_destination
+                    }),
                     promotion: None,
                 })
-            }
-
-            _ => None,
-        })
+            })
     }
 
     pub fn arbiter(&self, ply: &Ply) -> bool {
@@ -991,10 +1278,35 @@ impl Board {
         self.get_legal_moves(ply.origin).any(|x| &x == ply)
     }
 
+    /// Whether neither side can possibly checkmate: king against king, a lone minor piece
+    /// against a bare king, or any number of bishops that all stand on squares of one color
+    pub fn has_insufficient_material(&self) -> bool {
+        let mut minor_pieces = self
+            .pieces(Player::White)
+            .chain(self.pieces(Player::Black))
+            .filter(|piece| piece.kind != Kind::King);
+
+        let Some(first) = minor_pieces.next() else {
+            return true;
+        };
+
+        match first.kind {
+            Kind::Knight => minor_pieces.next().is_none(),
+            Kind::Bishop => {
+                let square_color = |coord: Coord| (coord.row + coord.col) % 2;
+                minor_pieces.all(|piece| {
+                    piece.kind == Kind::Bishop
+                        && square_color(piece.coord) == square_color(first.coord)
+                })
+            }
+            _ => false,
+        }
+    }
+
     pub fn verify_status(&self) -> Status {
         let king_pos = self.find_king(self.turn);
 
-        if self.half_move_clock == 100 {
+        if self.half_move_clock == 100 || self.has_insufficient_material() {
             return Status::Draw;
         }
 
@@ -1012,3 +1324,32 @@ impl Board {
         }
     }
 }
+
+/// Catches the malformed FEN the `fen` crate would panic on or misreport: every rank has
+/// to cover exactly 8 squares, and the en passant square has to be ASCII
+fn check_fen_syntax(fen: &str) -> Result<(), FenError> {
+    let mut fields = fen.split_whitespace();
+
+    if let Some(placement) = fields.next() {
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::BadPlacement(placement.to_string()));
+        }
+
+        for rank in ranks {
+            let squares: u32 = rank.chars().map(|c| c.to_digit(10).unwrap_or(1)).sum();
+            if squares > 8 {
+                return Err(FenError::TooManyPieces(rank.to_string()));
+            }
+            if squares < 8 {
+                return Err(FenError::BadPlacement(rank.to_string()));
+            }
+        }
+    }
+
+    if let Some(en_passant) = fields.nth(2).filter(|field| !field.is_ascii()) {
+        return Err(FenError::BadEnPassant(en_passant.to_string()));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use crate::{board::Board, piece::Kind, ply::Ply, square::Square};
+use std::fmt;
+
+/// A move packed into 16 bits: the origin square in bits 0-5, the destination in bits 6-11
+/// and flags describing the kind of move in bits 12-15.
+/// Unlike a `Ply`, a `Move` knows whether it captures, castles or takes en passant, so it
+/// can be built with `Board::encode_move` from the position it is played in
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Move(u16);
+
+/// Flags that don't describe a move: the unused values 6 and 7, anything that doesn't fit
+/// in 4 bits, or a promotion passed to `Move::new`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidMoveError(pub u16);
+
+impl fmt::Display for InvalidMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid move flags {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidMoveError {}
+
+impl Move {
+    pub const QUIET: u16 = 0;
+    pub const DOUBLE_PAWN_PUSH: u16 = 1;
+    pub const KING_CASTLE: u16 = 2;
+    pub const QUEEN_CASTLE: u16 = 3;
+    pub const CAPTURE: u16 = 4;
+    pub const EN_PASSANT: u16 = 5;
+    /// Set on every promotion, with the piece in the two lowest bits and `CAPTURE` on top
+    /// if the promoting pawn captures
+    pub const PROMOTION: u16 = 8;
+
+    // Order of the promotion pieces in the two lowest flag bits
+    const PROMOTION_KINDS: [Kind; 4] = [Kind::Knight, Kind::Bishop, Kind::Rook, Kind::Queen];
+
+    /// Builds a move that isn't a promotion from its squares and one of the flags above.
+    /// Promotions are built with `Move::new_promotion` instead
+    pub fn new(origin: Square, destination: Square, flags: u16) -> Result<Move, InvalidMoveError> {
+        if flags >= Move::PROMOTION || !Move::is_valid_flags(flags) {
+            return Err(InvalidMoveError(flags));
+        }
+        Ok(Move::pack(origin, destination, flags))
+    }
+
+    /// Builds a pawn move promoting to `kind`, which must be a knight, bishop, rook or queen
+    pub fn new_promotion(origin: Square, destination: Square, kind: Kind, capture: bool) -> Move {
+        let piece = Move::PROMOTION_KINDS
+            .iter()
+            .position(|&k| k == kind)
+            .expect("pawns can only promote to a knight, bishop, rook or queen")
+            as u16;
+        let capture = if capture { Move::CAPTURE } else { 0 };
+        let flags = Move::PROMOTION | capture | piece;
+        Move::pack(origin, destination, flags)
+    }
+
+    // 6 and 7 would be en passant and castling flags with `CAPTURE` set, which don't exist
+    fn is_valid_flags(flags: u16) -> bool {
+        flags < 16 && !matches!(flags, 6 | 7)
+    }
+
+    fn pack(origin: Square, destination: Square, flags: u16) -> Move {
+        Move(u16::from(u8::from(origin)) | u16::from(u8::from(destination)) << 6 | flags << 12)
+    }
+
+    pub fn origin(self) -> Square {
+        Square::from_index((self.0 & 0x3f) as usize).unwrap()
+    }
+
+    pub fn destination(self) -> Square {
+        Square::from_index((self.0 >> 6 & 0x3f) as usize).unwrap()
+    }
+
+    pub fn flags(self) -> u16 {
+        self.0 >> 12
+    }
+
+    /// Whether the move takes a piece, including en passant and capturing promotions
+    pub fn is_capture(self) -> bool {
+        self.flags() & Move::CAPTURE != 0
+    }
+
+    pub fn is_castle(self) -> bool {
+        matches!(self.flags(), Move::KING_CASTLE | Move::QUEEN_CASTLE)
+    }
+
+    pub fn is_en_passant(self) -> bool {
+        self.flags() == Move::EN_PASSANT
+    }
+
+    pub fn is_double_pawn_push(self) -> bool {
+        self.flags() == Move::DOUBLE_PAWN_PUSH
+    }
+
+    /// Piece the pawn promotes to, if the move is a promotion
+    pub fn promotion(self) -> Option<Kind> {
+        (self.flags() & Move::PROMOTION != 0)
+            .then(|| Move::PROMOTION_KINDS[(self.flags() & 0b11) as usize])
+    }
+}
+
+impl From<Move> for Ply {
+    fn from(m: Move) -> Ply {
+        Ply {
+            origin: m.origin().coord(),
+            destination: m.destination().coord(),
+            promotion: m.promotion(),
+        }
+    }
+}
+
+/// Decodes a move from its 16 bits, as produced by `From<Move> for u16`
+impl TryFrom<u16> for Move {
+    type Error = InvalidMoveError;
+
+    fn try_from(bits: u16) -> Result<Self, Self::Error> {
+        let flags = bits >> 12;
+        if Move::is_valid_flags(flags) {
+            Ok(Move(bits))
+        } else {
+            Err(InvalidMoveError(flags))
+        }
+    }
+}
+
+impl From<Move> for u16 {
+    fn from(m: Move) -> u16 {
+        m.0
+    }
+}
+
+/// Formats the move in UCI long algebraic notation, like `Ply`
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Ply::from(*self).fmt(f)
+    }
+}
+
+impl Board {
+    /// Packs a move of this position into a `Move`, working out its flags from the pieces
+    /// on the board. `ply` must move a piece of the side to move
+    pub fn encode_move(&self, ply: Ply) -> Move {
+        let origin = Square::from_coord(ply.origin).expect("origin must be on the board");
+        let destination =
+            Square::from_coord(ply.destination).expect("destination must be on the board");
+        let kind = self
+            .kind_at_square(ply.origin)
+            .expect("a move needs a piece on its origin square");
+        let is_capture = self.is_capture(ply);
+
+        if let Some(promotion) = ply.promotion {
+            return Move::new_promotion(origin, destination, promotion, is_capture);
+        }
+
+        let castling = self.castling_move(ply);
+        let flags = match kind {
+            Kind::King if castling.is_some_and(|castling| castling.kingside) => Move::KING_CASTLE,
+            Kind::King if castling.is_some() => Move::QUEEN_CASTLE,
+            Kind::Pawn if (ply.destination.row - ply.origin.row).abs() == 2 => {
+                Move::DOUBLE_PAWN_PUSH
+            }
+            Kind::Pawn if is_capture && self.get_piece_by_coord(ply.destination).is_none() => {
+                Move::EN_PASSANT
+            }
+            _ if is_capture => Move::CAPTURE,
+            _ => Move::QUIET,
+        };
+        Move::new(origin, destination, flags).expect("flags of a move that isn't a promotion")
+    }
+}
@@ -1,43 +1,397 @@
-use crate::{board::Board, piece::Kind, player::Player, ply::Ply};
+use crate::{
+    board::{Board, MoveFilter},
+    move_list::MoveList,
+    piece::Kind,
+    player::Player,
+    ply::Ply,
+    transposition::{Bound, Entry, TranspositionTable},
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
-pub fn search(board: Board, depth: i32) -> i32 {
-    let move_vec: Vec<Ply> = board.get_all_moves().collect(); // remove collect, use iter resturn value directly
+/// Score of a checkmate delivered at the root. Mates further away score lower, so the
+/// search prefers the quickest mate and delays being mated for as long as possible
+pub const CHECKMATE: i32 = 1_000_000_000;
+const INFINITY: i32 = CHECKMATE + 1;
 
-    if depth == 0 {
-        return evaluate(board);
+pub const MAX_DEPTH: i32 = 64;
+
+// Move ordering scores: the move from the transposition table, then captures and
+// promotions by `capture_order`, which stays well below the gap between the two
+const TABLE_MOVE_SCORE: i32 = 1_000_000;
+const CAPTURE_SCORE: i32 = 100_000;
+
+// How many nodes are searched between checks of the clock
+const NODES_BETWEEN_CHECKS: u64 = 1024;
+
+/// How much time the engine is allowed to spend on a move
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimeControl {
+    /// Search until the depth limit is reached or the search is stopped externally
+    #[default]
+    Infinite,
+    /// Spend exactly this long on the move
+    MoveTime(Duration),
+    /// Remaining time on the clock of the side to move, and its increment per move
+    Clock { time: Duration, increment: Duration },
+}
+
+impl TimeControl {
+    /// How long we are willing to think on the current move, if there is a limit at all
+    pub fn budget(&self) -> Option<Duration> {
+        match *self {
+            TimeControl::Infinite => None,
+            TimeControl::MoveTime(time) => Some(time),
+            TimeControl::Clock { time, increment } => {
+                // Keep a small margin so we never lose on time because of overhead
+                Some(
+                    (time / 30 + increment / 2).min(time.saturating_sub(Duration::from_millis(50))),
+                )
+            }
+        }
     }
+}
 
-    if move_vec.is_empty() {
-        match board.turn {
-            Player::Black => {
-                if board.is_square_attacked(board.black_king_loc, board.turn.opponent()) {
-                    return -1000000000;
-                }
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SearchLimits {
+    pub depth: Option<i32>,
+    pub time_control: TimeControl,
+}
+
+/// Result of one completed iteration of `iterative_deepening`
+#[derive(Debug, Clone, Copy)]
+pub struct SearchInfo {
+    pub depth: i32,
+    pub best_move: Ply,
+    pub evaluation: i32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+/// State shared by every node of a single search
+struct Searcher<'a> {
+    stop: &'a AtomicBool,
+    deadline: Option<Instant>,
+    table: &'a mut TranspositionTable,
+    nodes: u64,
+    aborted: bool,
+}
+
+impl<'a> Searcher<'a> {
+    fn new(
+        stop: &'a AtomicBool,
+        deadline: Option<Instant>,
+        table: &'a mut TranspositionTable,
+    ) -> Self {
+        Searcher {
+            stop,
+            deadline,
+            table,
+            nodes: 0,
+            aborted: false,
+        }
+    }
+
+    /// Checks whether the search should be abandoned.
+    /// The stop flag is cheap to read and checked at every node, but the clock is only read
+    /// every `NODES_BETWEEN_CHECKS` nodes
+    fn should_abort(&mut self) -> bool {
+        if !self.aborted {
+            self.aborted = self.stop.load(Ordering::Relaxed)
+                || self.nodes.is_multiple_of(NODES_BETWEEN_CHECKS)
+                    && self
+                        .deadline
+                        .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+        self.aborted
+    }
+
+    /// Generates the legal moves of `board`, scored so the best move from the table is
+    /// tried first, then captures and promotions, then quiet moves
+    fn ordered_moves(&self, board: &Board, table_move: Option<Ply>) -> MoveList {
+        let mut moves = board.legal_moves();
+        moves.set_scores(|ply| {
+            if Some(ply) == table_move {
+                TABLE_MOVE_SCORE
+            } else if board.is_capture(ply) || ply.promotion.is_some() {
+                CAPTURE_SCORE + capture_order(board, ply)
+            } else {
+                0
             }
-            Player::White => {
-                if board.is_square_attacked(board.white_king_loc, board.turn.opponent()) {
-                    return -1000000000;
-                }
+        });
+        moves
+    }
+
+    /// Searches every root move. If the search is aborted midway, the best move among the
+    /// ones fully searched so far is returned.
+    /// Moves are made and unmade on `board`, which is left as it was on return
+    fn search_root(&mut self, board: &mut Board, depth: i32) -> (Option<Ply>, i32) {
+        let key = board.zobrist_hash();
+        let table_move = self.table.probe(key).and_then(|entry| entry.best_move);
+        let mut best_ply = None;
+        let mut alpha = -INFINITY;
+
+        let mut moves = self.ordered_moves(board, table_move);
+        for index in 0..moves.len() {
+            let ply = moves.pick_best(index);
+            let undo = board.make_move_mut(ply);
+            let evaluation = -self.alpha_beta(board, depth - 1, -INFINITY, -alpha, 1);
+            board.unmake_move(undo);
+
+            if self.aborted {
+                break;
+            }
+
+            if evaluation > alpha {
+                alpha = evaluation;
+                best_ply = Some(ply);
+            }
+        }
+
+        if best_ply.is_none() {
+            return (None, self.alpha_beta(board, depth, -INFINITY, INFINITY, 0));
+        }
+
+        if !self.aborted {
+            self.table.store(Entry {
+                key,
+                depth,
+                evaluation: alpha,
+                bound: Bound::Exact,
+                best_move: best_ply,
+            });
+        }
+        (best_ply, alpha)
+    }
+
+    fn alpha_beta(
+        &mut self,
+        board: &mut Board,
+        depth: i32,
+        mut alpha: i32,
+        beta: i32,
+        ply_from_root: i32,
+    ) -> i32 {
+        self.nodes += 1;
+        if self.should_abort() {
+            return 0;
+        }
+
+        let key = board.zobrist_hash();
+        let entry = self.table.probe(key).copied();
+
+        if let Some(entry) = entry.filter(|entry| entry.depth >= depth) {
+            let evaluation = evaluation_from_table(entry.evaluation, ply_from_root);
+            match entry.bound {
+                Bound::Exact => return evaluation,
+                Bound::Lower if evaluation >= beta => return beta,
+                Bound::Upper if evaluation <= alpha => return alpha,
+                _ => {}
+            }
+        }
+
+        let mut moves = self.ordered_moves(board, entry.and_then(|entry| entry.best_move));
+
+        if moves.is_empty() {
+            if board.is_in_check() {
+                return -CHECKMATE + ply_from_root;
+            }
+            return 0;
+        }
+
+        if depth <= 0 {
+            return self.quiescence(board, alpha, beta);
+        }
+
+        let mut best_ply = None;
+
+        for index in 0..moves.len() {
+            let ply = moves.pick_best(index);
+            let undo = board.make_move_mut(ply);
+            let evaluation = -self.alpha_beta(board, depth - 1, -beta, -alpha, ply_from_root + 1);
+            board.unmake_move(undo);
+
+            if self.aborted {
+                return 0;
+            }
+
+            if evaluation >= beta {
+                self.table.store(Entry {
+                    key,
+                    depth,
+                    evaluation: evaluation_to_table(beta, ply_from_root),
+                    bound: Bound::Lower,
+                    best_move: Some(ply),
+                });
+                return beta;
             }
+            if evaluation > alpha {
+                alpha = evaluation;
+                best_ply = Some(ply);
+            }
+        }
+
+        self.table.store(Entry {
+            key,
+            depth,
+            evaluation: evaluation_to_table(alpha, ply_from_root),
+            bound: if best_ply.is_some() {
+                Bound::Exact
+            } else {
+                Bound::Upper
+            },
+            best_move: best_ply,
+        });
+        alpha
+    }
+
+    /// Extends the search with captures and promotions until the position is quiet, so the
+    /// evaluation isn't taken in the middle of an exchange.
+    /// The side to move may also decline every capture and keep the static evaluation
+    fn quiescence(&mut self, board: &mut Board, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        if self.should_abort() {
+            return 0;
         }
-        return 0;
+
+        let stand_pat = evaluate(board);
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut captures = board.filtered_moves(MoveFilter::Captures);
+        captures.set_scores(|ply| capture_order(board, ply));
+
+        for index in 0..captures.len() {
+            let ply = captures.pick_best(index);
+            let undo = board.make_move_mut(ply);
+            let evaluation = -self.quiescence(board, -beta, -alpha);
+            board.unmake_move(undo);
+
+            if self.aborted {
+                return 0;
+            }
+
+            if evaluation >= beta {
+                return beta;
+            }
+            alpha = alpha.max(evaluation);
+        }
+
+        alpha
+    }
+}
+
+/// Most valuable victim, least valuable attacker: prefer winning big pieces with small ones
+fn capture_order(board: &Board, ply: Ply) -> i32 {
+    let victim = match board.get_piece_by_coord(ply.destination) {
+        Some(piece) => piece.kind.value(),
+        None if board.is_capture(ply) => Kind::PAWN_VALUE, // en passant
+        None => 0,
+    };
+    let attacker = board
+        .get_piece_by_coord(ply.origin)
+        .map_or(0, |piece| piece.kind.value());
+    let promotion = ply.promotion.map_or(0, |kind| kind.value());
+
+    10 * (victim + promotion) - attacker
+}
+
+/// Mate scores are stored relative to the node rather than the root, so they stay correct
+/// when the position is reached again at a different distance from the root
+fn evaluation_to_table(evaluation: i32, ply_from_root: i32) -> i32 {
+    if is_mate_score(evaluation) {
+        evaluation + evaluation.signum() * ply_from_root
+    } else {
+        evaluation
+    }
+}
+
+fn evaluation_from_table(evaluation: i32, ply_from_root: i32) -> i32 {
+    if is_mate_score(evaluation) {
+        evaluation - evaluation.signum() * ply_from_root
+    } else {
+        evaluation
     }
+}
 
-    let mut best_evaluation = -1000000000;
+/// Searches `board` to `depth` plies with alpha-beta pruning, sharing `table` with earlier
+/// searches like `iterative_deepening` does.
+/// Returns the best move (`None` if there are no legal moves) and its evaluation from the
+/// perspective of the side to move
+pub fn search(board: &Board, depth: i32, table: &mut TranspositionTable) -> (Option<Ply>, i32) {
+    let stop = AtomicBool::new(false);
+    Searcher::new(&stop, None, table).search_root(&mut board.clone(), depth)
+}
+
+/// Searches `board` one depth at a time until `limits` are reached or `stop` is set,
+/// calling `on_iteration` after every completed depth.
+/// Returns the best move found so far and its evaluation
+pub fn iterative_deepening(
+    board: &Board,
+    limits: SearchLimits,
+    table: &mut TranspositionTable,
+    stop: &AtomicBool,
+    mut on_iteration: impl FnMut(&SearchInfo),
+) -> (Option<Ply>, i32) {
+    let start = Instant::now();
+    let budget = limits.time_control.budget();
+    let mut searcher = Searcher::new(stop, budget.map(|budget| start + budget), table);
+    let mut result = (None, 0);
+    let mut position = board.clone();
 
-    for i in 0..move_vec.len() {
-        let new_board_state = board.make_move(move_vec[i]);
-        let evaluation = -search(new_board_state, depth - 1);
+    for depth in 1..=limits.depth.unwrap_or(MAX_DEPTH) {
+        let (best_move, evaluation) = searcher.search_root(&mut position, depth);
 
-        best_evaluation = best_evaluation.max(evaluation);
+        if searcher.aborted {
+            // A partially searched depth is only better than nothing
+            if result.0.is_none() {
+                result = (best_move, evaluation);
+            }
+            break;
+        }
+
+        let Some(best_move) = best_move else {
+            return (None, evaluation);
+        };
+        result = (Some(best_move), evaluation);
+
+        on_iteration(&SearchInfo {
+            depth,
+            best_move,
+            evaluation,
+            nodes: searcher.nodes,
+            elapsed: start.elapsed(),
+        });
+
+        // The next depth takes considerably longer, so don't start it past half the budget
+        if is_mate_score(evaluation) || budget.is_some_and(|budget| start.elapsed() * 2 >= budget) {
+            break;
+        }
     }
 
-    best_evaluation
+    if result.0.is_none() {
+        result.0 = board.get_all_moves().next();
+    }
+    result
+}
+
+/// Whether `evaluation` means one of the sides has a forced mate
+pub fn is_mate_score(evaluation: i32) -> bool {
+    evaluation.abs() > CHECKMATE - 1000
+}
+
+/// Number of moves (not plies) until mate for a mate score, negative if the side to move
+/// is the one getting mated
+pub fn moves_to_mate(evaluation: i32) -> i32 {
+    let plies = CHECKMATE - evaluation.abs();
+    evaluation.signum() * (plies + 1) / 2
 }
 
-pub fn evaluate(board: Board) -> i32 {
-    let white_eval = count_material(&board, Player::White);
-    let black_eval = count_material(&board, Player::Black);
+pub fn evaluate(board: &Board) -> i32 {
+    let white_eval = count_material(board, Player::White);
+    let black_eval = count_material(board, Player::Black);
 
     let mult = match board.turn {
         Player::Black => -1,
@@ -52,7 +406,7 @@ fn count_material(board: &Board, player: Player) -> i32 {
 
     match player {
         Player::Black => {
-            for p in &board.black_pieces {
+            for p in board.pieces(Player::Black) {
                 match p.kind {
                     Kind::Pawn => result += Kind::PAWN_VALUE,
                     Kind::Rook => result += Kind::ROOK_VALUE,
@@ -64,7 +418,7 @@ fn count_material(board: &Board, player: Player) -> i32 {
             }
         }
         Player::White => {
-            for p in &board.white_pieces {
+            for p in board.pieces(Player::White) {
                 match p.kind {
                     Kind::Pawn => result += Kind::PAWN_VALUE,
                     Kind::Rook => result += Kind::ROOK_VALUE,
@@ -0,0 +1,80 @@
+use crate::{board::Board, coord::Coord, player::Player};
+use std::fmt;
+
+/// Reasons a FEN string can be rejected by `Board::from_fen`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    // Syntax errors
+    NotEnoughParts,
+    BadPlacement(String),
+    TooManyPieces(String),
+    UnknownPiece(char),
+    NoSuchSide(String),
+    BadCastling(String),
+    BadEnPassant(String),
+    BadHalfmove(String),
+    BadFullmove(String),
+
+    // Well-formed FEN describing an impossible position
+    MissingKing(Player),
+    TooManyKings(Player),
+    PawnOnBackRank(Coord),
+    InconsistentCastlingRights(char),
+    InconsistentEnPassant(Coord),
+    OpponentInCheck,
+}
+
+impl From<fen::FenError<'_>> for FenError {
+    fn from(error: fen::FenError<'_>) -> Self {
+        match error {
+            fen::FenError::NotEnoughParts => FenError::NotEnoughParts,
+            fen::FenError::BadPlacement(s) => FenError::BadPlacement(s.to_string()),
+            fen::FenError::TooManyPieces(s) => FenError::TooManyPieces(s.to_string()),
+            fen::FenError::UnknownPiece(c) => FenError::UnknownPiece(c),
+            fen::FenError::NoSuchSide(s) => FenError::NoSuchSide(s.to_string()),
+            fen::FenError::BadEnPassant(s) => FenError::BadEnPassant(s.to_string()),
+            fen::FenError::BadHalfmove(s) => FenError::BadHalfmove(s.to_string()),
+            fen::FenError::BadFullmove(s) => FenError::BadFullmove(s.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let square = |coord: &Coord| {
+            let (col, row) = Board::reverse_notation_conversion(*coord);
+            format!("{col}{row}")
+        };
+
+        match self {
+            FenError::NotEnoughParts => write!(f, "FEN must have exactly six fields"),
+            FenError::BadPlacement(s) => write!(f, "invalid piece placement \"{s}\""),
+            FenError::TooManyPieces(s) => write!(f, "too many squares in rank \"{s}\""),
+            FenError::UnknownPiece(c) => write!(f, "unknown piece '{c}'"),
+            FenError::NoSuchSide(s) => write!(f, "invalid side to move \"{s}\""),
+            FenError::BadCastling(s) => write!(f, "invalid castling rights \"{s}\""),
+            FenError::BadEnPassant(s) => write!(f, "invalid en passant square \"{s}\""),
+            FenError::BadHalfmove(s) => write!(f, "invalid halfmove clock \"{s}\""),
+            FenError::BadFullmove(s) => write!(f, "invalid fullmove number \"{s}\""),
+            FenError::MissingKing(player) => write!(f, "{player} has no king"),
+            FenError::TooManyKings(player) => write!(f, "{player} has more than one king"),
+            FenError::PawnOnBackRank(coord) => write!(f, "pawn on back rank at {}", square(coord)),
+            FenError::InconsistentCastlingRights(c) => {
+                write!(
+                    f,
+                    "castling right '{c}' without king and rook on their home squares"
+                )
+            }
+            FenError::InconsistentEnPassant(coord) => {
+                write!(
+                    f,
+                    "en passant square {} does not follow a double pawn push",
+                    square(coord)
+                )
+            }
+            FenError::OpponentInCheck => write!(f, "the side not to move is in check"),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
@@ -1,9 +1,27 @@
-use crate::{board::Board, coord::Coord, piece::Kind, ply::Ply};
+use crate::{
+    board::Board,
+    coord::Coord,
+    fen_error::FenError,
+    piece::Kind,
+    player::Player,
+    ply::Ply,
+    status::{GameResult, Termination},
+};
 use std::io;
 
 #[derive(Debug, Clone)]
 pub struct Game {
     pub states: Vec<Board>,
+    pub moves: Vec<Ply>, // moves[i] leads from states[i] to states[i + 1]
+    pub tags: Vec<(String, String)>, // PGN tag pairs, in insertion order
+    undone: Vec<Ply>,    // Moves taken back by `undo`, most recent last
+    resigned: Option<Player>, // Side that resigned, if any
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game {
@@ -12,8 +30,137 @@ impl Game {
     }
 
     pub fn new_from_fen(fen: &str) -> Game {
+        Game::from_board(Board::new_from_fen(fen))
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Game, FenError> {
+        Ok(Game::from_board(Board::from_fen(fen)?))
+    }
+
+    /// Starts a game of Chess960 from the starting position with the given number
+    pub fn new_chess960(index: usize) -> Game {
+        Game::from_board(Board::new_chess960(index))
+    }
+
+    fn from_board(board: Board) -> Game {
         Game {
-            states: vec![Board::new_from_fen(fen)],
+            states: vec![board],
+            moves: Vec::new(),
+            tags: Vec::new(),
+            undone: Vec::new(),
+            resigned: None,
+        }
+    }
+
+    pub fn current_position(&self) -> &Board {
+        self.states.last().unwrap()
+    }
+
+    pub fn last_move(&self) -> Option<Ply> {
+        self.moves.last().copied()
+    }
+
+    /// Every move played so far, together with the position it was played in
+    pub fn history(&self) -> impl Iterator<Item = (&Board, Ply)> {
+        self.states.iter().zip(self.moves.iter().copied())
+    }
+
+    /// Takes back the last move. Returns it, or `None` if no move has been played
+    pub fn undo(&mut self) -> Option<Ply> {
+        let ply = self.moves.pop()?;
+        self.states.pop();
+        self.resigned = None;
+        self.undone.push(ply);
+        Some(ply)
+    }
+
+    /// Plays again the last move taken back by `undo`.
+    /// Playing any other move in the meantime discards the moves that could be redone
+    pub fn redo(&mut self) -> Option<Ply> {
+        let ply = self.undone.pop()?;
+        let next_pos = self.current_position().make_move(ply);
+        self.states.push(next_pos);
+        self.moves.push(ply);
+        Some(ply)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.moves.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Value of the tag pair with the given name
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets a tag pair, replacing its value if the tag already exists
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old_value)) => *old_value = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Plays `ply` on the current position if it is legal.
+    /// Returns whether the move was played.
+    pub fn make_move(&mut self, ply: Ply) -> bool {
+        let current_pos = self.states.last().unwrap();
+
+        if !current_pos.arbiter(&ply) {
+            return false;
+        }
+
+        self.states.push(current_pos.make_move(ply));
+        self.moves.push(ply);
+        self.undone.clear();
+        true
+    }
+
+    /// Ends the game with a win for the opponent of `player`
+    pub fn resign(&mut self, player: Player) {
+        self.resigned = Some(player);
+    }
+
+    /// How the game ended, or `None` while it is still going on.
+    /// Threefold repetition and the fifty-move rule end the game as soon as a draw could be
+    /// claimed; if play goes on anyway, fivefold repetition and the seventy-five-move rule
+    /// are reported instead
+    pub fn result(&self) -> Option<GameResult> {
+        if let Some(player) = self.resigned {
+            return Some(GameResult::win(player.opponent(), Termination::Resignation));
+        }
+
+        let current_pos = self.current_position();
+
+        if current_pos.get_all_moves().next().is_none() {
+            return Some(if current_pos.is_in_check() {
+                GameResult::win(current_pos.turn.opponent(), Termination::Checkmate)
+            } else {
+                GameResult::draw(Termination::Stalemate)
+            });
+        }
+
+        let repetitions = self.repetition_count();
+
+        if current_pos.has_insufficient_material() {
+            Some(GameResult::draw(Termination::InsufficientMaterial))
+        } else if repetitions >= 5 {
+            Some(GameResult::draw(Termination::FivefoldRepetition))
+        } else if current_pos.half_move_clock() >= 150 {
+            Some(GameResult::draw(Termination::SeventyFiveMoveRule))
+        } else if current_pos.half_move_clock() >= 100 {
+            Some(GameResult::draw(Termination::FiftyMoveRule))
+        } else if repetitions >= 3 {
+            Some(GameResult::draw(Termination::ThreefoldRepetition))
+        } else {
+            None
         }
     }
 
@@ -23,60 +170,51 @@ impl Game {
 
             self.states.last().unwrap().print_board(current_pos.turn);
 
-            let Some(ply) = self.get_user_move() else {
+            println!("Move?");
+            let mut input = String::new();
+            io::stdin().read_line(&mut input).unwrap();
+
+            match input.trim() {
+                "undo" => {
+                    if self.undo().is_none() {
+                        println!("There is no move to undo.");
+                    }
+                    continue;
+                }
+                "redo" => {
+                    if self.redo().is_none() {
+                        println!("There is no move to redo.");
+                    }
+                    continue;
+                }
+                "resign" => {
+                    self.resign(current_pos.turn);
+                    println!("{}", self.result().unwrap());
+                    break;
+                }
+                _ => {}
+            }
+
+            let Some(ply) = self.parse_user_move(&input) else {
                 println!("Invalid input text.");
                 continue;
             };
 
-            if !current_pos.arbiter(&ply) {
+            if !self.make_move(ply) {
                 println!("That move is not allowed, idiot.");
                 continue;
             }
 
-            self.states.push(current_pos.make_move(ply));
-            let next_pos = self.states.last().unwrap();
-
-            if self.verify_threefold_repetition() {
+            if let Some(result) = self.result() {
+                let next_pos = self.current_position();
                 next_pos.print_board(next_pos.turn);
-                println!("Draw");
+                println!("{result}");
                 break;
             }
-
-            println!("{:?}", next_pos.black_king_loc);
-            println!("{:?}", next_pos.white_king_loc);
-
-            match next_pos.verify_status() {
-                crate::status::Status::Invalid => {
-                    next_pos.print_board(next_pos.turn);
-                    println!("Invalid position");
-                    break;
-                }
-                crate::status::Status::BWin => {
-                    next_pos.print_board(next_pos.turn);
-                    println!("Checkmate: black wins");
-                    break;
-                }
-                crate::status::Status::WWin => {
-                    next_pos.print_board(next_pos.turn);
-                    println!("Checkmate: white wins");
-                    break;
-                }
-                crate::status::Status::Draw => {
-                    next_pos.print_board(next_pos.turn);
-                    println!("Draw");
-                    break;
-                }
-                crate::status::Status::Ongoing => continue,
-            }
         }
     }
 
-    fn get_user_move(&self) -> Option<Ply> {
-        println!("Move?");
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-
+    fn parse_user_move(&self, input: &str) -> Option<Ply> {
         let parse_coord = |input: &str| -> Option<Coord> {
             let mut iter = input.trim().chars();
             let c1 = iter.next()?;
@@ -101,25 +239,39 @@ impl Game {
 
         let items = input.trim().split(" ").collect::<Vec<&str>>();
 
+        // Moves can also be given as `e2e4`/`e7e8q` or in SAN
+        if items.len() == 1 {
+            let current_pos = self.states.last().unwrap();
+            return current_pos
+                .parse_uci_move(items[0])
+                .or_else(|_| current_pos.parse_san(items[0]).map_err(|_| ()))
+                .ok();
+        }
+
         Some(Ply {
-            origin: parse_coord(items.get(0)?)?,
+            origin: parse_coord(items.first()?)?,
             destination: parse_coord(items.get(1)?)?,
             promotion: items.get(2).and_then(|&s| parse_promotion(s)),
         })
     }
 
-    fn verify_threefold_repetition(&self) -> bool {
-        let current_state = match self.states.last() {
-            Some(state) => state,
-            None => return false,
-        };
+    /// How many times the current position has occurred in the game, itself included.
+    /// Only positions since the last capture or pawn move are considered, as none before it
+    /// can occur again
+    pub fn repetition_count(&self) -> usize {
+        let current_pos = self.current_position();
+        let reversible_plies = current_pos.half_move_clock() as usize;
 
         self.states
             .iter()
             .rev()
+            .take(reversible_plies + 1)
             .step_by(2)
-            .filter(|&x| x == current_state)
+            .filter(|state| state.is_same_position(current_pos))
             .count()
-            >= 3
+    }
+
+    pub fn verify_threefold_repetition(&self) -> bool {
+        self.repetition_count() >= 3
     }
 }
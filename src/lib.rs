@@ -0,0 +1,38 @@
+//! Chess move generation, game management and search.
+//!
+//! The binary in `main.rs` is a thin command-line front-end over this crate, which also
+//! exposes a UCI front-end in [`uci`]. Other tools can depend on it directly to work with
+//! boards, games and the engine.
+
+pub mod bitboard;
+pub mod board;
+pub mod chess_move;
+pub mod coord;
+pub mod engine;
+pub mod fen_error;
+pub mod game;
+pub mod move_list;
+pub mod perft;
+pub mod pgn;
+pub mod piece;
+pub mod player;
+pub mod ply;
+pub mod san;
+pub mod square;
+pub mod status;
+pub mod transposition;
+pub mod uci;
+pub mod zobrist;
+
+pub use board::{Board, MoveFilter, Undo};
+pub use chess_move::{InvalidMoveError, Move};
+pub use coord::Coord;
+pub use fen_error::FenError;
+pub use game::Game;
+pub use move_list::MoveList;
+pub use piece::{Kind, Piece};
+pub use player::Player;
+pub use ply::{ParsePlyError, Ply};
+pub use san::SanError;
+pub use square::{ParseSquareError, Square};
+pub use status::{GameResult, Status, Termination};
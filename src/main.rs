@@ -1,42 +1,80 @@
-#![allow(dead_code)]
-
-mod board;
-mod coord;
-mod engine;
-mod game;
-mod perft;
-mod piece;
-mod player;
-mod ply;
-mod status;
+use chess_engine::{game::Game, perft, uci, Board};
 
 fn main() {
     std::env::set_var("RUST_BACKTRACE", "1");
 
-    // game::Game::new_from_fen("1r2kr2/pp1p1p2/2p4p/6pP/P1PP4/1P6/5PP1/R3K2R w KQ g6 0 21").play();
-    // game::Game::new_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b K- e3 0 1").play();
-    // let mut game = game::Game::new_from_fen(
-    //     "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
-    // );
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-    // game::Game::new_from_fen("6n1/p1qkp3/3p4/P3P3/5B2/2P1K3/7P/R7 w - - 0 1").play();
+    match args.first().map(String::as_str) {
+        None | Some("play") => match args.get(1) {
+            Some(fen) => match Game::from_fen(fen) {
+                Ok(mut game) => game.play(),
+                Err(error) => {
+                    eprintln!("Invalid FEN: {error}");
+                    std::process::exit(1);
+                }
+            },
+            None => Game::new().play(),
+        },
+        Some("perft") if args.len() == 1 => perft::perft_suite(),
+        Some("perft") => match parse_perft_args(&args[1..]) {
+            Ok((board, depth, threads, divide)) => perft::run_perft(&board, depth, threads, divide),
+            Err(error) => {
+                eprintln!("{error}");
+                eprintln!("{PERFT_USAGE}");
+                std::process::exit(1);
+            }
+        },
+        Some("uci") => uci::run(),
+        Some(other) => {
+            eprintln!("Unknown command: {other}");
+            eprintln!("Usage: chess-engine [play [FEN] | perft [DEPTH [FEN] [OPTIONS]] | uci]");
+            std::process::exit(1);
+        }
+    }
+}
+
+const PERFT_USAGE: &str = "Usage: chess-engine perft DEPTH [FEN] [--divide] [--threads N]\n\
+     Without a depth, runs the built-in suite of positions";
 
-    // let mut game = game::Game::new();
-    // game.play();
-    // let game_six = game::Game::new_from_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",);
-    // // println!("{}", perft::perft(*game_six.states.last().unwrap(), 2));
+/// Parses `DEPTH [FEN] [--divide] [--threads N]`. The FEN may be passed as a single quoted
+/// argument or as its separate fields
+fn parse_perft_args(args: &[String]) -> Result<(Board, i32, usize, bool), String> {
+    let mut depth = None;
+    let mut fen_fields = Vec::new();
+    let mut threads = 1;
+    let mut divide = false;
 
-    // let game =
-    //     game::Game::new_from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B3n1/P7/1PP1N1PP/RNBQ1RK1 b - - 2 9");
-    // let board = game.states.last().unwrap();
-    // // println!("{:?}", board.new_get_legal_moves(Coord { row: 1, col: 3 }));
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--divide" => divide = true,
+            "--threads" => {
+                threads = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|&n| n > 0)
+                    .ok_or("--threads needs a positive number")?;
+            }
+            _ if depth.is_none() => {
+                // Perft only stops at depth 0, so a negative depth would never return
+                depth = Some(
+                    arg.parse()
+                        .ok()
+                        .filter(|&depth: &i32| depth >= 0)
+                        .ok_or_else(|| format!("Invalid depth: {arg}"))?,
+                );
+            }
+            _ => fen_fields.push(arg.as_str()),
+        }
+    }
 
-    // println!(
-    //     "{}",
-    //     board.is_square_attacked(Coord { row: 1, col: 5 }, player::Player::Black),
-    // );
+    let depth = depth.ok_or("Missing depth")?;
+    let board = if fen_fields.is_empty() {
+        Game::new().current_position().clone()
+    } else {
+        Board::from_fen(&fen_fields.join(" ")).map_err(|error| format!("Invalid FEN: {error}"))?
+    };
 
-    perft::perft_suite();
-    // perft::perft_one_pos();
-    // perft::perft_divider();
+    Ok((board, depth, threads, divide))
 }
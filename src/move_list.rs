@@ -0,0 +1,149 @@
+use crate::{coord::Coord, ply::Ply};
+use std::ops::Deref;
+
+/// No legal position has more moves than this
+pub const MAX_MOVES: usize = 256;
+
+const EMPTY: Ply = Ply {
+    origin: Coord { row: 0, col: 0 },
+    destination: Coord { row: 0, col: 0 },
+    promotion: None,
+};
+
+/// Fixed-capacity list of moves kept on the stack, so generating moves never allocates.
+/// Every move has a score slot for move ordering, which starts at 0
+#[derive(Debug, Clone)]
+pub struct MoveList {
+    moves: [Ply; MAX_MOVES],
+    scores: [i32; MAX_MOVES],
+    len: usize,
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MoveList {
+    pub fn new() -> MoveList {
+        MoveList {
+            moves: [EMPTY; MAX_MOVES],
+            scores: [0; MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, ply: Ply) {
+        self.moves[self.len] = ply;
+        self.scores[self.len] = 0;
+        self.len += 1;
+    }
+
+    /// Keeps only the moves for which `keep` returns true, in their original order
+    pub fn retain(&mut self, mut keep: impl FnMut(Ply) -> bool) {
+        let mut kept = 0;
+        for i in 0..self.len {
+            if keep(self.moves[i]) {
+                self.moves[kept] = self.moves[i];
+                self.scores[kept] = self.scores[i];
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+
+    pub fn score(&self, index: usize) -> i32 {
+        self.scores[..self.len][index]
+    }
+
+    /// Fills the score slot of every move with `score_of`
+    pub fn set_scores(&mut self, mut score_of: impl FnMut(Ply) -> i32) {
+        for i in 0..self.len {
+            self.scores[i] = score_of(self.moves[i]);
+        }
+    }
+
+    /// Swaps the highest scoring move from `index` onwards into `index` and returns it.
+    /// Calling this for every index in turn visits the moves from best to worst, and
+    /// search that cuts off early doesn't pay for sorting the rest
+    pub fn pick_best(&mut self, index: usize) -> Ply {
+        let mut best = index;
+        for i in index + 1..self.len {
+            if self.scores[i] > self.scores[best] {
+                best = i;
+            }
+        }
+        self.moves[..self.len].swap(index, best);
+        self.scores.swap(index, best);
+        self.moves[index]
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Ply];
+
+    fn deref(&self) -> &[Ply] {
+        &self.moves[..self.len]
+    }
+}
+
+impl Extend<Ply> for MoveList {
+    fn extend<I: IntoIterator<Item = Ply>>(&mut self, iter: I) {
+        for ply in iter {
+            self.push(ply);
+        }
+    }
+}
+
+impl FromIterator<Ply> for MoveList {
+    fn from_iter<I: IntoIterator<Item = Ply>>(iter: I) -> Self {
+        let mut moves = MoveList::new();
+        moves.extend(iter);
+        moves
+    }
+}
+
+/// Iterator over the moves of a `MoveList`, taking ownership of it
+pub struct IntoIter {
+    list: MoveList,
+    index: usize,
+}
+
+impl Iterator for IntoIter {
+    type Item = Ply;
+
+    fn next(&mut self) -> Option<Ply> {
+        let ply = self.list.get(self.index).copied()?;
+        self.index += 1;
+        Some(ply)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.list.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl IntoIterator for MoveList {
+    type Item = Ply;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter {
+            list: self,
+            index: 0,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Ply;
+    type IntoIter = std::slice::Iter<'a, Ply>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
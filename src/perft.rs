@@ -1,4 +1,4 @@
-use crate::{board::Board, game::Game};
+use crate::{board::Board, game::Game, ply::Ply};
 use rayon::prelude::*;
 use std::time::Instant;
 
@@ -18,8 +18,9 @@ pub fn perft(board: &Board, depth: i32, parallel: i32) -> u64 {
             .sum()
     } else {
         board
-            .get_all_moves()
-            .map(|m| {
+            .legal_moves()
+            .iter()
+            .map(|&m| {
                 let new_board_state = board.make_move(m);
                 perft(&new_board_state, depth - 1, parallel - 1)
             })
@@ -27,48 +28,70 @@ pub fn perft(board: &Board, depth: i32, parallel: i32) -> u64 {
     }
 }
 
-pub fn perft_divider() {
-    let depth = 1;
-    let fen = "rnbq1k1r/pp1Pbppp/2p5/8/2B3n1/P7/1PP1N1PP/RNBQ1RK1 b - - 2 9";
-    let game = Game::new_from_fen(fen);
-    let board = game.states.last().unwrap();
-    let mut result = 0;
-
-    Board::check_everything(board);
-    board.get_all_moves().for_each(|ply| {
-        let new_board_state = board.make_move(ply);
-
-        let accum = perft(&new_board_state, depth - 1, 1);
+/// Same count as `perft`, but walks the tree on a single board with
+/// `make_move_mut`/`unmake_move` instead of copying it at every node
+pub fn perft_in_place(board: &mut Board, depth: i32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
 
-        result += accum;
-        println!(
-            "{}{} {}{}: {}",
-            Board::reverse_notation_conversion(ply.origin).0,
-            Board::reverse_notation_conversion(ply.origin).1,
-            Board::reverse_notation_conversion(ply.destination).0,
-            Board::reverse_notation_conversion(ply.destination).1,
-            accum
-        );
-        // println!("{:?}", new_board_state.get_all_moves());
-    });
+    board
+        .legal_moves()
+        .into_iter()
+        .map(|m| {
+            let undo = board.make_move_mut(m);
+            let nodes = perft_in_place(board, depth - 1);
+            board.unmake_move(undo);
+            nodes
+        })
+        .sum()
+}
 
-    println!("Total nodes: {}", result);
+/// Node count below each legal move of `board`, sorted by the move's UCI notation
+pub fn perft_divide(board: &Board, depth: i32, parallel: i32) -> Vec<(Ply, u64)> {
+    let mut counts: Vec<(Ply, u64)> = board
+        .get_all_moves()
+        .map(|ply| (ply, perft(&board.make_move(ply), depth - 1, parallel)))
+        .collect();
+    counts.sort_by_key(|(ply, _)| ply.to_string());
+    counts
 }
 
-pub fn perft_one_pos() -> () {
-    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
-    let checks = vec![(1, 6), (2, 264), (3, 9467), (4, 422333)];
+/// Runs perft on `board` with `threads` worker threads and prints the result in the same
+/// format as other engines, so the output can be diffed against theirs.
+/// With `divide`, the count below every root move is printed as well
+pub fn run_perft(board: &Board, depth: i32, threads: usize, divide: bool) {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .expect("failed to start the perft threads");
+    // Splitting the first two levels gives every thread plenty of work
+    let parallel = if threads > 1 { 2 } else { 0 };
 
-    let game = Game::new_from_fen(fen);
-    for (depth, value) in checks {
-        let result = perft(game.states.last().unwrap(), depth, 1);
-        println!("Position {fen}, depth {depth}, result {result}, expected {value}");
-        // assert_eq!(result, value);
-    }
+    let start = Instant::now();
+    let nodes = pool.install(|| {
+        if divide && depth > 0 {
+            let counts = perft_divide(board, depth, parallel);
+            for (ply, count) in &counts {
+                println!("{ply}: {count}");
+            }
+            println!();
+            counts.iter().map(|(_, count)| count).sum()
+        } else {
+            perft(board, depth, parallel)
+        }
+    });
+    let duration = start.elapsed();
+
+    println!("Nodes searched: {nodes}");
+    eprintln!(
+        "Took {duration:?}, {:.0} nodes per second",
+        nodes as f64 / duration.as_secs_f64()
+    );
 }
 
 //
-pub fn perft_suite() -> () {
+pub fn perft_suite() {
     let data = [
         (
             "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
@@ -78,8 +101,7 @@ pub fn perft_suite() -> () {
                 (3, 8902),
                 (4, 197281),
                 (5, 4865609),
-                (6, 1),
-                (7, 1),
+                (6, 119060324),
             ],
         ),
         (
@@ -111,7 +133,7 @@ pub fn perft_suite() -> () {
             let result = perft(game.states.last().unwrap(), depth, 2);
             let duration = Instant::now().duration_since(start);
             println!("Position {fen}, depth {depth}, result {result}, expected {value}, took {duration:?}, speed {:.2}", result as f64 / duration.as_secs_f64());
-            // assert_eq!(result, value);
+            assert_eq!(result, value);
         }
     }
 }
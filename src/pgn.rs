@@ -0,0 +1,463 @@
+use crate::{fen_error::FenError, game::Game, player::Player, san::SanError};
+use std::fmt;
+
+const STANDARD_START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Tags every PGN game must have, in the order they must be exported in
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+// Export format lines must not be longer than this
+const MAX_LINE_LENGTH: usize = 79;
+
+/// Reasons PGN text can't be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    InvalidTag(String),
+    UnterminatedComment,
+    UnterminatedVariation,
+    UnmatchedParenthesis,
+    InvalidFen(FenError),
+    /// A move of the main line that can't be played, with the move number and side it was
+    /// found at
+    IllegalMove {
+        move_number: u64,
+        player: Player,
+        san: String,
+        reason: SanError,
+    },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::InvalidTag(tag) => write!(f, "invalid tag pair \"{tag}\""),
+            PgnError::UnterminatedComment => write!(f, "comment is never closed"),
+            PgnError::UnterminatedVariation => write!(f, "variation is never closed"),
+            PgnError::UnmatchedParenthesis => write!(f, "')' without a matching '('"),
+            PgnError::InvalidFen(error) => write!(f, "invalid FEN tag: {error}"),
+            PgnError::IllegalMove {
+                move_number,
+                player,
+                san,
+                reason,
+            } => {
+                let dots = match player {
+                    Player::White => ".",
+                    Player::Black => "...",
+                };
+                write!(f, "move {move_number}{dots} {san}: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl From<FenError> for PgnError {
+    fn from(error: FenError) -> Self {
+        PgnError::InvalidFen(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Tag(String, String),
+    Move(String),
+    Result(String),
+}
+
+/// Reads the first game of a PGN file. Nothing after that game is read, so a broken
+/// game later in the file doesn't matter.
+/// The result at the end of the movetext is stored as the `Result` tag if the game has none
+pub fn read_pgn(text: &str) -> Result<Game, PgnError> {
+    pgn_games(text)
+        .next()
+        .unwrap_or_else(|| build_game(Vec::new()))
+}
+
+/// Reads every game of a PGN file, such as an archive of many games
+pub fn read_pgn_games(text: &str) -> Result<Vec<Game>, PgnError> {
+    pgn_games(text).collect()
+}
+
+/// Iterates over the games of a PGN file, reading each one only when it is asked for
+pub fn pgn_games(text: &str) -> PgnGames {
+    PgnGames {
+        tokens: Tokens::new(text),
+        next_tag: None,
+    }
+}
+
+/// Iterator over the games of a PGN file, returned by `pgn_games`
+pub struct PgnGames {
+    tokens: Tokens,
+    // Tag pair that ended the previous game by starting this one
+    next_tag: Option<Token>,
+}
+
+impl Iterator for PgnGames {
+    type Item = Result<Game, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut current: Vec<Token> = self.next_tag.take().into_iter().collect();
+
+        loop {
+            let token = match self.tokens.next() {
+                Some(Ok(token)) => token,
+                Some(Err(error)) => return Some(Err(error)),
+                None if current.is_empty() => return None,
+                None => return Some(build_game(current)),
+            };
+
+            // A tag pair after movetext starts a new game, even if the previous one had no result
+            let starts_new_game = matches!(token, Token::Tag(..))
+                && current.iter().any(|t| !matches!(t, Token::Tag(..)));
+            if starts_new_game {
+                self.next_tag = Some(token);
+                return Some(build_game(current));
+            }
+
+            let is_result = matches!(token, Token::Result(_));
+            current.push(token);
+            if is_result {
+                return Some(build_game(current));
+            }
+        }
+    }
+}
+
+/// Replays the tokens of a single game
+fn build_game(tokens: Vec<Token>) -> Result<Game, PgnError> {
+    let mut tags = Vec::new();
+    let mut moves = Vec::new();
+    let mut result = None;
+
+    for token in tokens {
+        match token {
+            Token::Tag(name, value) => tags.push((name, value)),
+            Token::Move(san) => moves.push(san),
+            Token::Result(r) => result = Some(r),
+        }
+    }
+
+    let mut game = match tags.iter().find(|(name, _)| name == "FEN") {
+        Some((_, fen)) => Game::from_fen(fen)?,
+        None => Game::new(),
+    };
+    let is_chess960 = tags
+        .iter()
+        .any(|(name, value)| name == "Variant" && value.eq_ignore_ascii_case("chess960"));
+    if is_chess960 {
+        game.states.last_mut().unwrap().set_chess960(true);
+    }
+
+    for san in moves {
+        let current_pos = game.states.last().unwrap();
+        let ply = current_pos
+            .parse_san(&san)
+            .map_err(|reason| PgnError::IllegalMove {
+                move_number: current_pos.full_move_number(),
+                player: current_pos.turn,
+                san: san.clone(),
+                reason,
+            })?;
+        game.make_move(ply);
+    }
+
+    game.tags = tags;
+    if let Some(result) = result.filter(|_| game.tag("Result").is_none()) {
+        game.set_tag("Result", &result);
+    }
+
+    Ok(game)
+}
+
+/// Splits PGN text into tokens one at a time, skipping comments, variations and
+/// annotations. Stops for good after the first error
+struct Tokens {
+    chars: Vec<char>,
+    i: usize,
+}
+
+impl Tokens {
+    fn new(text: &str) -> Tokens {
+        Tokens {
+            chars: text.chars().collect(),
+            i: 0,
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, PgnError> {
+        let chars = &self.chars;
+        let mut i = self.i;
+
+        while i < chars.len() {
+            let c = chars[i];
+
+            // `%` at the start of a line escapes the whole line
+            let at_line_start = i == 0 || chars[i - 1] == '\n';
+
+            match c {
+                _ if c.is_whitespace() => i += 1,
+                '%' if at_line_start => i = skip_line(chars, i),
+                ';' => i = skip_line(chars, i),
+                '{' => i = skip_comment(chars, i)?,
+                '(' => i = skip_variation(chars, i)?,
+                ')' => return Err(PgnError::UnmatchedParenthesis),
+                '[' => {
+                    let (token, next) = read_tag(chars, i)?;
+                    self.i = next;
+                    return Ok(Some(token));
+                }
+                '$' => {
+                    // Numeric annotation glyph
+                    i += 1;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                _ => {
+                    let start = i;
+                    while i < chars.len()
+                        && !chars[i].is_whitespace()
+                        && !"{}()[];$".contains(chars[i])
+                    {
+                        i += 1;
+                    }
+                    let symbol: String = chars[start..i].iter().collect();
+
+                    let token = match symbol.as_str() {
+                        "1-0" | "0-1" | "1/2-1/2" | "*" => Some(Token::Result(symbol)),
+                        _ => {
+                            // Move numbers such as `12.` or `12...`, possibly glued to the move
+                            let rest = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+                            let san = if rest.len() < symbol.len()
+                                && (rest.is_empty() || rest.starts_with('.'))
+                            {
+                                rest.trim_start_matches('.')
+                            } else {
+                                &symbol
+                            };
+
+                            (!san.is_empty()).then(|| Token::Move(san.to_string()))
+                        }
+                    };
+
+                    if let Some(token) = token {
+                        self.i = i;
+                        return Ok(Some(token));
+                    }
+                }
+            }
+        }
+
+        self.i = i;
+        Ok(None)
+    }
+}
+
+impl Iterator for Tokens {
+    type Item = Result<Token, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.next_token();
+        if token.is_err() {
+            self.i = self.chars.len();
+        }
+        token.transpose()
+    }
+}
+
+fn skip_line(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() && chars[i] != '\n' {
+        i += 1;
+    }
+    i
+}
+
+fn skip_comment(chars: &[char], i: usize) -> Result<usize, PgnError> {
+    chars[i..]
+        .iter()
+        .position(|&c| c == '}')
+        .map(|offset| i + offset + 1)
+        .ok_or(PgnError::UnterminatedComment)
+}
+
+/// Skips a variation, including nested variations and comments inside it
+fn skip_variation(chars: &[char], mut i: usize) -> Result<usize, PgnError> {
+    let mut depth = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(i + 1);
+                }
+            }
+            '{' => {
+                i = skip_comment(chars, i)?;
+                continue;
+            }
+            ';' => {
+                i = skip_line(chars, i);
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    Err(PgnError::UnterminatedVariation)
+}
+
+/// Reads a tag pair like `[Event "F/S Return Match"]`
+fn read_tag(chars: &[char], i: usize) -> Result<(Token, usize), PgnError> {
+    let mut j = i + 1;
+    let mut name = String::new();
+    let mut value = String::new();
+    let invalid = || PgnError::InvalidTag(chars[i..skip_line(chars, i)].iter().collect());
+
+    while j < chars.len() && chars[j].is_whitespace() {
+        j += 1;
+    }
+    while j < chars.len() && (chars[j].is_alphanumeric() || chars[j] == '_') {
+        name.push(chars[j]);
+        j += 1;
+    }
+    while j < chars.len() && chars[j].is_whitespace() {
+        j += 1;
+    }
+
+    if name.is_empty() || chars.get(j) != Some(&'"') {
+        return Err(invalid());
+    }
+    j += 1;
+
+    loop {
+        match chars.get(j) {
+            Some('\\') if matches!(chars.get(j + 1), Some('"' | '\\')) => {
+                value.push(chars[j + 1]);
+                j += 2;
+            }
+            Some('"') => {
+                j += 1;
+                break;
+            }
+            Some(&c) if c != '\n' => {
+                value.push(c);
+                j += 1;
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    while j < chars.len() && chars[j].is_whitespace() {
+        j += 1;
+    }
+    if chars.get(j) != Some(&']') {
+        return Err(invalid());
+    }
+
+    Ok((Token::Tag(name, value), j + 1))
+}
+
+impl Game {
+    /// Writes the game in PGN export format: the Seven Tag Roster first, then the remaining
+    /// tags, then the movetext in SAN.
+    /// The result is taken from `Game::result` when the game is over, along with a
+    /// `Termination` tag and a comment before the result saying how the game ended, and
+    /// from the `Result` tag otherwise
+    pub fn to_pgn(&self) -> String {
+        let result = self.pgn_result();
+        let game_result = self.result();
+        let start = self.states.first().unwrap();
+        let start_fen = start.to_fen();
+
+        let mut tags: Vec<(&str, &str)> = SEVEN_TAG_ROSTER
+            .iter()
+            .map(|&(name, default)| match name {
+                "Result" => (name, result),
+                _ => (name, self.tag(name).unwrap_or(default)),
+            })
+            .collect();
+        // Other readers would take a Chess960 game for standard chess without the variant,
+        // so those always name it and their start position, even if it is the standard one
+        if start.is_chess960() {
+            tags.push(("Variant", "Chess960"));
+        }
+        if start_fen != STANDARD_START || start.is_chess960() {
+            tags.push(("SetUp", "1"));
+            tags.push(("FEN", &start_fen));
+        }
+        if let Some(game_result) = game_result {
+            tags.push(("Termination", game_result.termination.pgn_tag()));
+        }
+        for (name, value) in &self.tags {
+            if !tags.iter().any(|(tag, _)| tag == name) {
+                tags.push((name, value));
+            }
+        }
+
+        let mut pgn = String::new();
+        for (name, value) in tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{name} \"{value}\"]\n"));
+        }
+        pgn.push('\n');
+
+        let mut symbols = Vec::new();
+        for (board, ply) in self.history() {
+            match board.turn {
+                Player::White => symbols.push(format!("{}.", board.full_move_number())),
+                Player::Black if symbols.is_empty() => {
+                    symbols.push(format!("{}...", board.full_move_number()))
+                }
+                Player::Black => {}
+            }
+            symbols.push(board.ply_to_san(ply));
+        }
+        if let Some(game_result) = game_result {
+            symbols.push(format!("{{{game_result}}}"));
+        }
+        symbols.push(result.to_string());
+
+        let mut line = String::new();
+        for symbol in symbols {
+            if !line.is_empty() && line.len() + 1 + symbol.len() > MAX_LINE_LENGTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&symbol);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+
+        pgn
+    }
+
+    /// PGN result of the game: decided by `Game::result` if the game is over, otherwise
+    /// whatever the `Result` tag says, e.g. for a game adjudicated elsewhere
+    fn pgn_result(&self) -> &str {
+        match self.result() {
+            Some(result) => result.score(),
+            None => match self.tag("Result") {
+                Some(result @ ("1-0" | "0-1" | "1/2-1/2")) => result,
+                _ => "*",
+            },
+        }
+    }
+}
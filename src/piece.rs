@@ -7,7 +7,6 @@ pub struct Piece {
     pub kind: Kind,
     pub player: Player,
     pub coord: Coord,
-    pub idx: usize,
 }
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct PieceCopy {
@@ -41,6 +40,18 @@ impl Kind {
     pub const ROOK_VALUE: i32 = 500;
     pub const QUEEN_VALUE: i32 = 900;
 
+    /// Material value in centipawns. The king is priceless, so it is worth nothing here
+    pub fn value(&self) -> i32 {
+        match self {
+            Kind::Pawn => Kind::PAWN_VALUE,
+            Kind::Knight => Kind::KNIGHT_VALUE,
+            Kind::Bishop => Kind::BISHOP_VALUE,
+            Kind::Rook => Kind::ROOK_VALUE,
+            Kind::Queen => Kind::QUEEN_VALUE,
+            Kind::King => 0,
+        }
+    }
+
     fn character(&self, player: Player) -> char {
         match player {
             Player::White => match self {
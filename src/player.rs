@@ -2,9 +2,10 @@ use std::fmt;
 
 use crate::coord::Coord;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Player {
     Black,
+    #[default]
     White,
 }
 
@@ -38,12 +39,6 @@ impl Player {
     }
 }
 
-impl Default for Player {
-    fn default() -> Self {
-        Player::White
-    }
-}
-
 impl fmt::Display for Player {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -1,4 +1,6 @@
-use crate::{coord::Coord, piece::Kind};
+use crate::{board::Board, coord::Coord, piece::Kind};
+use std::fmt;
+use std::str::FromStr;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Ply {
@@ -6,3 +8,87 @@ pub struct Ply {
     pub destination: Coord,
     pub promotion: Option<Kind>,
 }
+
+/// Reasons a UCI move string like `e7e8q` can be rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePlyError {
+    InvalidSyntax(String),
+    IllegalMove(String),
+}
+
+impl fmt::Display for ParsePlyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsePlyError::InvalidSyntax(s) => write!(f, "\"{s}\" is not a valid UCI move"),
+            ParsePlyError::IllegalMove(s) => write!(f, "{s} is not a legal move"),
+        }
+    }
+}
+
+impl std::error::Error for ParsePlyError {}
+
+/// Formats the move in UCI long algebraic notation, e.g. `e2e4` or `e7e8q`
+impl fmt::Display for Ply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (origin_col, origin_row) = Board::reverse_notation_conversion(self.origin);
+        let (destination_col, destination_row) =
+            Board::reverse_notation_conversion(self.destination);
+        write!(
+            f,
+            "{origin_col}{origin_row}{destination_col}{destination_row}"
+        )?;
+
+        match self.promotion {
+            Some(Kind::Queen) => write!(f, "q"),
+            Some(Kind::Rook) => write!(f, "r"),
+            Some(Kind::Bishop) => write!(f, "b"),
+            Some(Kind::Knight) => write!(f, "n"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Parses a move in UCI long algebraic notation. This only checks the syntax;
+/// use `Board::parse_uci_move` to also check that the move is legal
+impl FromStr for Ply {
+    type Err = ParsePlyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParsePlyError::InvalidSyntax(s.to_string());
+        let chars: Vec<char> = s.chars().collect();
+        if chars.len() != 4 && chars.len() != 5 {
+            return Err(invalid());
+        }
+
+        let parse_coord =
+            |col: char, row: char| Board::notation_conversion(col, row.to_digit(10)? as i32);
+        let origin = parse_coord(chars[0], chars[1]).ok_or_else(invalid)?;
+        let destination = parse_coord(chars[2], chars[3]).ok_or_else(invalid)?;
+        let promotion = match chars.get(4) {
+            None => None,
+            Some('q') => Some(Kind::Queen),
+            Some('r') => Some(Kind::Rook),
+            Some('b') => Some(Kind::Bishop),
+            Some('n') => Some(Kind::Knight),
+            Some(_) => return Err(invalid()),
+        };
+
+        Ok(Ply {
+            origin,
+            destination,
+            promotion,
+        })
+    }
+}
+
+impl Board {
+    /// Parses a move in UCI long algebraic notation and checks it is legal in this position
+    pub fn parse_uci_move(&self, s: &str) -> Result<Ply, ParsePlyError> {
+        let ply: Ply = s.parse()?;
+        if self.arbiter(&ply) {
+            Ok(ply)
+        } else {
+            Err(ParsePlyError::IllegalMove(s.to_string()))
+        }
+    }
+}
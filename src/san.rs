@@ -0,0 +1,203 @@
+use crate::{board::Board, coord::Coord, piece::Kind, ply::Ply};
+use std::fmt;
+
+/// Reasons a SAN string can't be resolved to a move by `Board::parse_san`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SanError {
+    InvalidSyntax(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SanError::InvalidSyntax(san) => write!(f, "\"{san}\" is not valid SAN"),
+            SanError::IllegalMove(san) => write!(f, "{san} is not a legal move"),
+            SanError::AmbiguousMove(san) => write!(f, "{san} matches more than one legal move"),
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+fn kind_letter(kind: Kind) -> Option<char> {
+    match kind {
+        Kind::Pawn => None,
+        Kind::Knight => Some('N'),
+        Kind::Bishop => Some('B'),
+        Kind::Rook => Some('R'),
+        Kind::Queen => Some('Q'),
+        Kind::King => Some('K'),
+    }
+}
+
+fn letter_kind(letter: char) -> Option<Kind> {
+    match letter {
+        'N' => Some(Kind::Knight),
+        'B' => Some(Kind::Bishop),
+        'R' => Some(Kind::Rook),
+        'Q' => Some(Kind::Queen),
+        'K' => Some(Kind::King),
+        _ => None,
+    }
+}
+
+fn square_name(coord: Coord) -> String {
+    let (col, row) = Board::reverse_notation_conversion(coord);
+    format!("{col}{row}")
+}
+
+impl Board {
+    /// Formats a legal move in Standard Algebraic Notation, e.g. `Nbd7`, `exd5`, `O-O`,
+    /// `e8=Q+` or `Qh4#`
+    pub fn ply_to_san(&self, ply: Ply) -> String {
+        let piece = self
+            .get_piece_by_coord(ply.origin)
+            .expect("SAN can only be written for a move of an existing piece");
+        let is_capture = self.is_capture(ply);
+
+        let mut san = if let Some(castling) = self.castling_move(ply) {
+            if castling.kingside {
+                "O-O".to_string()
+            } else {
+                "O-O-O".to_string()
+            }
+        } else if piece.kind == Kind::Pawn {
+            let mut san = String::new();
+            if is_capture {
+                san.push(Board::reverse_notation_conversion(ply.origin).0);
+                san.push('x');
+            }
+            san.push_str(&square_name(ply.destination));
+            if let Some(letter) = ply.promotion.and_then(kind_letter) {
+                san.push('=');
+                san.push(letter);
+            }
+            san
+        } else {
+            // Other pieces of the same kind that could also reach the destination
+            let rivals: Vec<Coord> = self
+                .get_all_moves()
+                .filter(|other| {
+                    other.destination == ply.destination
+                        && other.origin != ply.origin
+                        && self.kind_at_square(other.origin) == Some(piece.kind)
+                })
+                .map(|other| other.origin)
+                .collect();
+
+            let (file, rank) = Board::reverse_notation_conversion(ply.origin);
+            let mut san = String::from(kind_letter(piece.kind).unwrap());
+            if !rivals.is_empty() {
+                if rivals.iter().all(|c| c.col != ply.origin.col) {
+                    san.push(file);
+                } else if rivals.iter().all(|c| c.row != ply.origin.row) {
+                    san.push_str(&rank.to_string());
+                } else {
+                    san.push(file);
+                    san.push_str(&rank.to_string());
+                }
+            }
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&square_name(ply.destination));
+            san
+        };
+
+        let next_pos = self.make_move(ply);
+        if next_pos.is_in_check() {
+            if next_pos.get_all_moves().next().is_none() {
+                san.push('#');
+            } else {
+                san.push('+');
+            }
+        }
+
+        san
+    }
+
+    /// Resolves a move in Standard Algebraic Notation against the legal moves of the
+    /// position. Check marks and annotations such as `!?` are accepted and ignored
+    pub fn parse_san(&self, san: &str) -> Result<Ply, SanError> {
+        let invalid = || SanError::InvalidSyntax(san.to_string());
+        let text = san.trim_end_matches(['+', '#', '!', '?']);
+
+        let candidates: Vec<Ply> = match text {
+            "O-O" | "0-0" | "O-O-O" | "0-0-0" => {
+                let kingside = text.len() == 3;
+                self.get_all_moves()
+                    .filter(|&ply| {
+                        self.castling_move(ply)
+                            .is_some_and(|castling| castling.kingside == kingside)
+                    })
+                    .collect()
+            }
+            _ => {
+                let mut chars: Vec<char> = text.chars().collect();
+
+                // Promotion, written either as `e8=Q` or `e8Q`
+                let promotion = match chars.last().copied().and_then(letter_kind) {
+                    Some(kind) if chars.len() > 2 => {
+                        chars.pop();
+                        if chars.last() == Some(&'=') {
+                            chars.pop();
+                        }
+                        Some(kind)
+                    }
+                    _ => None,
+                };
+
+                let kind = match chars.first().copied().and_then(letter_kind) {
+                    Some(kind) => {
+                        chars.remove(0);
+                        kind
+                    }
+                    None => Kind::Pawn,
+                };
+
+                if chars.len() < 2 {
+                    return Err(invalid());
+                }
+                let rank = chars.pop().unwrap().to_digit(10).ok_or_else(invalid)?;
+                let file = chars.pop().unwrap();
+                let destination =
+                    Board::notation_conversion(file, rank as i32).ok_or_else(invalid)?;
+
+                let is_capture = chars.last() == Some(&'x');
+                if is_capture {
+                    chars.pop();
+                }
+
+                // Whatever is left disambiguates the origin square
+                let mut from_col = None;
+                let mut from_row = None;
+                for c in chars {
+                    match c {
+                        'a'..='h' if from_col.is_none() => from_col = Some(c as i32 - 'a' as i32),
+                        '1'..='8' if from_row.is_none() => from_row = Some(c as i32 - '1' as i32),
+                        _ => return Err(invalid()),
+                    }
+                }
+
+                self.get_all_moves()
+                    .filter(|&ply| {
+                        ply.destination == destination
+                            && ply.promotion == promotion
+                            && self.kind_at_square(ply.origin) == Some(kind)
+                            && self.castling_move(ply).is_none()
+                            && from_col.is_none_or(|col| ply.origin.col == col)
+                            && from_row.is_none_or(|row| ply.origin.row == row)
+                    })
+                    .collect()
+            }
+        };
+
+        match candidates[..] {
+            [ply] => Ok(ply),
+            [] => Err(SanError::IllegalMove(san.to_string())),
+            _ => Err(SanError::AmbiguousMove(san.to_string())),
+        }
+    }
+}
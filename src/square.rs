@@ -0,0 +1,93 @@
+use crate::coord::Coord;
+use std::fmt;
+use std::str::FromStr;
+
+/// One of the 64 squares, numbered like the bits of a bitboard: a1 is 0, h1 is 7 and h8
+/// is 63. Unlike a `Coord`, a `Square` is always on the board
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Square(u8);
+
+/// A string that isn't the name of a square, such as `i9`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSquareError(pub String);
+
+impl fmt::Display for ParseSquareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\" is not a square", self.0)
+    }
+}
+
+impl std::error::Error for ParseSquareError {}
+
+impl Square {
+    pub const A1: Square = Square(0);
+    pub const H1: Square = Square(7);
+    pub const A8: Square = Square(56);
+    pub const H8: Square = Square(63);
+
+    /// The square with bitboard index `index`, if it is below 64
+    pub fn from_index(index: usize) -> Option<Square> {
+        (index < 64).then_some(Square(index as u8))
+    }
+
+    /// The square at `coord`, or `None` if it is off the board
+    pub fn from_coord(coord: Coord) -> Option<Square> {
+        coord
+            .is_valid()
+            .then_some(Square((coord.row * 8 + coord.col) as u8))
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// File from 0 (a) to 7 (h)
+    pub fn file(self) -> u8 {
+        self.0 % 8
+    }
+
+    /// Rank from 0 (the first rank) to 7 (the eighth)
+    pub fn rank(self) -> u8 {
+        self.0 / 8
+    }
+
+    pub fn coord(self) -> Coord {
+        Coord {
+            row: self.rank() as i32,
+            col: self.file() as i32,
+        }
+    }
+}
+
+impl From<Square> for Coord {
+    fn from(square: Square) -> Coord {
+        square.coord()
+    }
+}
+
+impl From<Square> for u8 {
+    fn from(square: Square) -> u8 {
+        square.0
+    }
+}
+
+/// Formats the square by its algebraic name, e.g. `e4`
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file()) as char, self.rank() + 1)
+    }
+}
+
+/// Parses the algebraic name of a square, e.g. `e4`
+impl FromStr for Square {
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+                Ok(Square((rank - b'1') * 8 + (file - b'a')))
+            }
+            _ => Err(ParseSquareError(s.to_string())),
+        }
+    }
+}
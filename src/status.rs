@@ -1,3 +1,6 @@
+use crate::player::Player;
+use std::fmt;
+
 #[derive(Debug, Clone, Copy)]
 pub enum Status {
     Invalid,
@@ -6,3 +9,92 @@ pub enum Status {
     Draw,
     Ongoing,
 }
+
+/// Why a game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
+    Resignation,
+    Stalemate,
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    InsufficientMaterial,
+    /// Drawn automatically, without either player claiming it
+    FivefoldRepetition,
+    /// Drawn automatically, without either player claiming it
+    SeventyFiveMoveRule,
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Termination::Checkmate => write!(f, "checkmate"),
+            Termination::Resignation => write!(f, "resignation"),
+            Termination::Stalemate => write!(f, "stalemate"),
+            Termination::FiftyMoveRule => write!(f, "fifty-move rule"),
+            Termination::ThreefoldRepetition => write!(f, "threefold repetition"),
+            Termination::InsufficientMaterial => write!(f, "insufficient material"),
+            Termination::FivefoldRepetition => write!(f, "fivefold repetition"),
+            Termination::SeventyFiveMoveRule => write!(f, "seventy-five-move rule"),
+        }
+    }
+}
+
+impl Termination {
+    /// Value of the PGN `Termination` tag, which only has a handful of standard values.
+    /// Every way a game can end here is decided over the board, which PGN calls "normal"
+    pub fn pgn_tag(&self) -> &'static str {
+        match self {
+            Termination::Checkmate
+            | Termination::Resignation
+            | Termination::Stalemate
+            | Termination::FiftyMoveRule
+            | Termination::ThreefoldRepetition
+            | Termination::InsufficientMaterial
+            | Termination::FivefoldRepetition
+            | Termination::SeventyFiveMoveRule => "normal",
+        }
+    }
+}
+
+/// Outcome of a finished game, as decided by `Game::result`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameResult {
+    /// `None` for a draw
+    pub winner: Option<Player>,
+    pub termination: Termination,
+}
+
+impl GameResult {
+    pub fn win(winner: Player, termination: Termination) -> Self {
+        GameResult {
+            winner: Some(winner),
+            termination,
+        }
+    }
+
+    pub fn draw(termination: Termination) -> Self {
+        GameResult {
+            winner: None,
+            termination,
+        }
+    }
+
+    /// The result as written in PGN: `1-0`, `0-1` or `1/2-1/2`
+    pub fn score(&self) -> &'static str {
+        match self.winner {
+            Some(Player::White) => "1-0",
+            Some(Player::Black) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.winner {
+            Some(winner) => write!(f, "{winner} wins by {}", self.termination),
+            None => write!(f, "Draw by {}", self.termination),
+        }
+    }
+}
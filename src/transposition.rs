@@ -0,0 +1,70 @@
+use crate::ply::Ply;
+
+/// How the stored evaluation relates to the true value of the position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The evaluation is exact
+    Exact,
+    /// The search failed high: the true value is at least the evaluation
+    Lower,
+    /// The search failed low: the true value is at most the evaluation
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: i32,
+    pub evaluation: i32,
+    pub bound: Bound,
+    pub best_move: Option<Ply>,
+}
+
+/// Fixed-size hash table of search results, indexed by Zobrist hash
+#[derive(Debug, Clone)]
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    pub const DEFAULT_SIZE_MB: usize = 16;
+
+    /// Creates a table taking up roughly `size_mb` megabytes
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        let len = (size_mb * 1024 * 1024 / std::mem::size_of::<Option<Entry>>()).max(1);
+        TranspositionTable {
+            entries: vec![None; len],
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+
+    /// Looks up the entry for a position, if one is stored
+    pub fn probe(&self, key: u64) -> Option<&Entry> {
+        self.entries[self.index(key)]
+            .as_ref()
+            .filter(|entry| entry.key == key)
+    }
+
+    /// Stores an entry, unless the slot already holds a deeper search of the same position
+    pub fn store(&mut self, entry: Entry) {
+        let idx = self.index(entry.key);
+        let slot = &mut self.entries[idx];
+
+        if slot.is_none_or(|old| old.key != entry.key || old.depth <= entry.depth) {
+            *slot = Some(entry);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SIZE_MB)
+    }
+}
@@ -0,0 +1,257 @@
+use crate::{
+    board::Board,
+    engine::{self, SearchLimits, TimeControl},
+    game::Game,
+    player::Player,
+    transposition::TranspositionTable,
+};
+use std::io::{self, BufRead};
+use std::str::SplitWhitespace;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const MAX_HASH_MB: usize = 1024;
+
+/// Time spent on a move when `go` has clock times but none for the side to move
+const FALLBACK_MOVE_TIME: Duration = Duration::from_secs(1);
+
+/// Parameters of a `go` command
+#[derive(Debug, Default, Clone, Copy)]
+struct GoParameters {
+    depth: Option<i32>,
+    movetime: Option<Duration>,
+    wtime: Option<Duration>,
+    btime: Option<Duration>,
+    winc: Option<Duration>,
+    binc: Option<Duration>,
+    infinite: bool,
+}
+
+impl GoParameters {
+    fn search_limits(&self, turn: Player) -> SearchLimits {
+        let clock = match turn {
+            Player::White => self.wtime.map(|time| (time, self.winc.unwrap_or_default())),
+            Player::Black => self.btime.map(|time| (time, self.binc.unwrap_or_default())),
+        };
+
+        let has_clock = [self.wtime, self.btime, self.winc, self.binc]
+            .iter()
+            .any(Option::is_some);
+
+        let time_control = match (self.infinite, self.movetime, clock) {
+            (true, ..) => TimeControl::Infinite,
+            (false, Some(movetime), _) => TimeControl::MoveTime(movetime),
+            (false, None, Some((time, increment))) => TimeControl::Clock { time, increment },
+            // A clock was sent, just not ours, so the game is timed and we can't think forever
+            (false, None, None) if has_clock => TimeControl::MoveTime(FALLBACK_MOVE_TIME),
+            (false, None, None) => TimeControl::Infinite,
+        };
+
+        SearchLimits {
+            depth: self.depth,
+            time_control,
+        }
+    }
+}
+
+/// Options set with `setoption`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UciOption {
+    /// Size of the transposition table in megabytes
+    Hash(usize),
+    /// Whether castling moves are sent and received as the king taking its own rook
+    Chess960(bool),
+}
+
+/// Runs the UCI protocol loop on stdin/stdout until `quit` is received or stdin is closed
+pub fn run() {
+    let mut game = Game::new();
+    let mut chess960 = false;
+    let table = Arc::new(Mutex::new(TranspositionTable::default()));
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut search_thread: Option<JoinHandle<()>> = None;
+
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("uci") => {
+                println!(
+                    "id name {} {}",
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION")
+                );
+                println!("id author arturchichorro");
+                println!(
+                    "option name Hash type spin default {} min 1 max {MAX_HASH_MB}",
+                    TranspositionTable::DEFAULT_SIZE_MB
+                );
+                println!("option name UCI_Chess960 type check default false");
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("setoption") => {
+                stop_search(&stop, &mut search_thread);
+                match parse_option(tokens) {
+                    Some(UciOption::Hash(size_mb)) => {
+                        *table.lock().unwrap() = TranspositionTable::new(size_mb);
+                    }
+                    Some(UciOption::Chess960(enabled)) => chess960 = enabled,
+                    None => {}
+                }
+            }
+            Some("ucinewgame") => {
+                stop_search(&stop, &mut search_thread);
+                table.lock().unwrap().clear();
+                game = Game::new();
+            }
+            Some("position") => {
+                stop_search(&stop, &mut search_thread);
+                match parse_position(tokens, chess960) {
+                    Some(new_game) => game = new_game,
+                    None => println!("info string invalid position command: {line}"),
+                }
+            }
+            Some("go") => {
+                stop_search(&stop, &mut search_thread);
+                let parameters = parse_go(tokens);
+                let board = game.states.last().unwrap().clone();
+                let table = Arc::clone(&table);
+                let stop = Arc::clone(&stop);
+                stop.store(false, Ordering::SeqCst);
+                search_thread = Some(thread::spawn(move || {
+                    think(board, parameters, &mut table.lock().unwrap(), &stop)
+                }));
+            }
+            Some("stop") => stop_search(&stop, &mut search_thread),
+            Some("quit") => {
+                stop_search(&stop, &mut search_thread);
+                break;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn stop_search(stop: &AtomicBool, search_thread: &mut Option<JoinHandle<()>>) {
+    stop.store(true, Ordering::SeqCst);
+    if let Some(handle) = search_thread.take() {
+        handle.join().unwrap();
+    }
+}
+
+/// Searches until a limit is hit, printing an `info` line per depth and finally the `bestmove`
+fn think(
+    board: Board,
+    parameters: GoParameters,
+    table: &mut TranspositionTable,
+    stop: &AtomicBool,
+) {
+    let limits = parameters.search_limits(board.turn);
+
+    let (best_move, _) = engine::iterative_deepening(&board, limits, table, stop, |info| {
+        let score = if engine::is_mate_score(info.evaluation) {
+            format!("mate {}", engine::moves_to_mate(info.evaluation))
+        } else {
+            format!("cp {}", info.evaluation)
+        };
+        let millis = info.elapsed.as_millis();
+        let nps = info.nodes as u128 * 1000 / millis.max(1);
+
+        println!(
+            "info depth {} score {score} nodes {} nps {nps} time {millis} pv {}",
+            info.depth, info.nodes, info.best_move
+        );
+    });
+
+    // In infinite mode the best move may only be sent after `stop`
+    while parameters.infinite && !stop.load(Ordering::SeqCst) {
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    match best_move {
+        Some(ply) => println!("bestmove {ply}"),
+        None => println!("bestmove 0000"),
+    }
+}
+
+/// Parses the position and plays its moves. With `chess960` the game is played as
+/// Chess960 whatever the FEN looks like
+fn parse_position(mut tokens: SplitWhitespace, chess960: bool) -> Option<Game> {
+    let mut game = match tokens.next()? {
+        "startpos" => {
+            if tokens.next().is_some_and(|token| token != "moves") {
+                return None;
+            }
+            Game::new()
+        }
+        "fen" => {
+            // Consumes the `moves` keyword, if present
+            let fen = tokens
+                .by_ref()
+                .take_while(|&token| token != "moves")
+                .collect::<Vec<&str>>()
+                .join(" ");
+            Game::from_fen(&fen).ok()?
+        }
+        _ => return None,
+    };
+
+    if chess960 {
+        game.states.last_mut().unwrap().set_chess960(true);
+    }
+    apply_moves(game, tokens)
+}
+
+fn apply_moves(mut game: Game, tokens: SplitWhitespace) -> Option<Game> {
+    for token in tokens {
+        let ply = game.states.last().unwrap().parse_uci_move(token).ok()?;
+        game.make_move(ply);
+    }
+    Some(game)
+}
+
+/// Parses `setoption name Hash value <mb>` or `setoption name UCI_Chess960 value <bool>`
+fn parse_option(tokens: SplitWhitespace) -> Option<UciOption> {
+    match tokens.collect::<Vec<&str>>()[..] {
+        ["name", name, "value", value] if name.eq_ignore_ascii_case("hash") => value
+            .parse()
+            .ok()
+            .map(|size_mb: usize| UciOption::Hash(size_mb.clamp(1, MAX_HASH_MB))),
+        ["name", name, "value", value] if name.eq_ignore_ascii_case("uci_chess960") => {
+            value.parse().ok().map(UciOption::Chess960)
+        }
+        _ => None,
+    }
+}
+
+fn parse_go(mut tokens: SplitWhitespace) -> GoParameters {
+    let mut parameters = GoParameters::default();
+
+    while let Some(token) = tokens.next() {
+        let mut next_millis = || {
+            tokens
+                .next()
+                .and_then(|value| value.parse::<u64>().ok())
+                .map(Duration::from_millis)
+        };
+
+        match token {
+            "depth" => parameters.depth = tokens.next().and_then(|value| value.parse().ok()),
+            "movetime" => parameters.movetime = next_millis(),
+            "wtime" => parameters.wtime = next_millis(),
+            "btime" => parameters.btime = next_millis(),
+            "winc" => parameters.winc = next_millis(),
+            "binc" => parameters.binc = next_millis(),
+            "infinite" => parameters.infinite = true,
+            _ => {}
+        }
+    }
+
+    parameters
+}
@@ -0,0 +1,89 @@
+use crate::{coord::Coord, piece::Kind, player::Player};
+
+/// Random keys used to compute Zobrist hashes of positions.
+/// A position's hash is the XOR of the keys of every feature present in it
+pub struct ZobristKeys {
+    pieces: [[[u64; 64]; 6]; 2],
+    pub black_to_move: u64,
+    castling: [u64; 4],
+    en_passant_file: [u64; 8],
+}
+
+pub static KEYS: ZobristKeys = ZobristKeys::generate(0x9E37_79B9_7F4A_7C15);
+
+impl ZobristKeys {
+    /// Fills the tables with a splitmix64 sequence, so the keys are fixed at compile time
+    const fn generate(seed: u64) -> Self {
+        let mut state = seed;
+        let mut keys = ZobristKeys {
+            pieces: [[[0; 64]; 6]; 2],
+            black_to_move: 0,
+            castling: [0; 4],
+            en_passant_file: [0; 8],
+        };
+
+        let mut player = 0;
+        while player < 2 {
+            let mut kind = 0;
+            while kind < 6 {
+                let mut square = 0;
+                while square < 64 {
+                    keys.pieces[player][kind][square] = splitmix64(&mut state);
+                    square += 1;
+                }
+                kind += 1;
+            }
+            player += 1;
+        }
+
+        keys.black_to_move = splitmix64(&mut state);
+
+        let mut i = 0;
+        while i < 4 {
+            keys.castling[i] = splitmix64(&mut state);
+            i += 1;
+        }
+
+        let mut i = 0;
+        while i < 8 {
+            keys.en_passant_file[i] = splitmix64(&mut state);
+            i += 1;
+        }
+
+        keys
+    }
+
+    pub fn piece(&self, kind: Kind, player: Player, coord: Coord) -> u64 {
+        let player_idx = match player {
+            Player::White => 0,
+            Player::Black => 1,
+        };
+        let kind_idx = match kind {
+            Kind::Pawn => 0,
+            Kind::Knight => 1,
+            Kind::Bishop => 2,
+            Kind::Rook => 3,
+            Kind::Queen => 4,
+            Kind::King => 5,
+        };
+
+        self.pieces[player_idx][kind_idx][(coord.row * 8 + coord.col) as usize]
+    }
+
+    /// Key for a castling right, indexed as White O-O, White O-O-O, Black O-O, Black O-O-O
+    pub fn castling(&self, right: usize) -> u64 {
+        self.castling[right]
+    }
+
+    pub fn en_passant_file(&self, col: i32) -> u64 {
+        self.en_passant_file[col as usize]
+    }
+}
+
+const fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,130 @@
+use chess_engine::{pgn::read_pgn, Board, Game, Move, Ply};
+
+fn uci_moves(board: &Board) -> Vec<String> {
+    board.get_all_moves().map(|ply| ply.to_string()).collect()
+}
+
+#[test]
+fn start_positions_from_index() {
+    assert_eq!(
+        Board::new_chess960(518).to_fen(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    );
+    assert_eq!(
+        Board::new_chess960(0).to_fen(),
+        "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+    );
+    assert_eq!(
+        Board::new_chess960(959).to_fen(),
+        "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w KQkq - 0 1"
+    );
+
+    let mut back_ranks: Vec<String> = (0..960)
+        .map(|index| {
+            let board = Board::new_chess960(index);
+            assert!(board.is_chess960());
+            board.to_fen()[..8].to_string()
+        })
+        .collect();
+    back_ranks.sort();
+    back_ranks.dedup();
+    assert_eq!(back_ranks.len(), 960);
+}
+
+#[test]
+fn castling_rights_in_shredder_and_x_fen() {
+    let shredder =
+        Board::new_from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9");
+    let x_fen =
+        Board::new_from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9");
+    assert!(shredder.is_chess960());
+    assert_eq!(shredder, x_fen);
+    assert_eq!(
+        shredder.to_fen(),
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9"
+    );
+
+    // With two rooks on the same side of the king, the inner one needs its file
+    let fen = "rr2k3/8/8/8/8/8/8/RR2K3 w Bb - 0 1";
+    let board = Board::new_from_fen(fen);
+    assert_eq!(board.to_fen(), fen);
+    assert!(uci_moves(&board).contains(&"e1b1".to_string()));
+    assert!(!uci_moves(&board).contains(&"e1a1".to_string()));
+
+    // Standard positions don't become Chess960 just because of the notation
+    assert!(
+        !Board::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+            .is_chess960()
+    );
+}
+
+#[test]
+fn castling_moves_king_and_rook_to_their_files() {
+    let board = Board::new_from_fen("4k3/8/8/8/8/8/8/R5KR w HA - 0 1");
+    let moves = uci_moves(&board);
+    assert!(moves.contains(&"g1h1".to_string()));
+    assert!(moves.contains(&"g1a1".to_string()));
+
+    // The king already stands on g1, so only the rook moves
+    let kingside: Ply = "g1h1".parse().unwrap();
+    assert_eq!(board.ply_to_san(kingside), "O-O");
+    assert_eq!(board.encode_move(kingside).flags(), Move::KING_CASTLE);
+    assert_eq!(
+        board.make_move(kingside).to_fen(),
+        "4k3/8/8/8/8/8/8/R4RK1 b - - 1 1"
+    );
+
+    let queenside = board.parse_san("O-O-O").unwrap();
+    assert_eq!(queenside.to_string(), "g1a1");
+    assert_eq!(
+        board.make_move(queenside).to_fen(),
+        "4k3/8/8/8/8/8/8/2KR3R b - - 1 1"
+    );
+
+    let mut in_place = board.clone();
+    let undo = in_place.make_move_mut(queenside);
+    in_place.unmake_move(undo);
+    assert_eq!(in_place, board);
+}
+
+#[test]
+fn castling_legality() {
+    // The king can't cross an attacked square
+    let board = Board::new_from_fen("3rk3/8/8/8/8/8/8/R5KR w HA - 0 1");
+    let moves = uci_moves(&board);
+    assert!(moves.contains(&"g1h1".to_string()));
+    assert!(!moves.contains(&"g1a1".to_string()));
+
+    // Nor land on a square the castling rook was shielding from an attack along the rank
+    let board = Board::new_from_fen("4k3/8/8/8/8/8/8/rR1K4 w B - 0 1");
+    assert!(!uci_moves(&board).contains(&"d1b1".to_string()));
+
+    // Every square the king and rook pass through must be empty, apart from themselves
+    let board = Board::new_from_fen("4k3/8/8/8/8/8/8/RN3KR1 w GA - 0 1");
+    let moves = uci_moves(&board);
+    assert!(moves.contains(&"f1g1".to_string()));
+    assert!(!moves.contains(&"f1a1".to_string()));
+}
+
+#[test]
+fn pgn_export_names_the_variant() {
+    // Position 518 looks like standard chess, so only the tags say otherwise
+    let mut game = Game::new_chess960(518);
+    for uci in ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1h1"] {
+        let ply = game.current_position().parse_uci_move(uci).unwrap();
+        assert!(game.make_move(ply));
+    }
+
+    let pgn = game.to_pgn();
+    assert!(pgn.contains("[Variant \"Chess960\"]\n[SetUp \"1\"]\n[FEN \"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\"]\n"));
+
+    let read = read_pgn(&pgn).unwrap();
+    assert!(read.states[0].is_chess960());
+    assert_eq!(
+        read.current_position().to_fen(),
+        game.current_position().to_fen()
+    );
+    assert!(Game::new_chess960(0)
+        .to_pgn()
+        .contains("[Variant \"Chess960\"]"));
+}
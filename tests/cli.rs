@@ -0,0 +1,16 @@
+use std::process::Command;
+
+#[test]
+fn negative_perft_depth_is_rejected() {
+    let output = Command::new(env!("CARGO_BIN_EXE_chess-engine"))
+        .args(["perft", "-1"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.starts_with("Invalid depth: -1\nUsage: chess-engine perft"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,132 @@
+use chess_engine::{
+    engine::{self, SearchLimits, TimeControl},
+    transposition::TranspositionTable,
+    Board, Game, Kind,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn quiescence_sees_the_recapture_at_the_horizon() {
+    // Taking the pawn on d5 with the queen looks good at depth 1, but e6 takes back
+    let board = Board::new_from_fen("k7/8/4p3/3p4/8/8/8/3Q3K w - - 0 1");
+    let (best_move, evaluation) = engine::search(&board, 1, &mut TranspositionTable::default());
+
+    assert_ne!(best_move.unwrap().to_string(), "d1d5");
+    assert_eq!(evaluation, Kind::QUEEN_VALUE - 2 * Kind::PAWN_VALUE);
+}
+
+#[test]
+fn finds_mate_in_one() {
+    let board = Board::new_from_fen("6k1/5ppp/8/8/8/8/8/R6K w - - 0 1");
+    let (best_move, evaluation) = engine::search(&board, 2, &mut TranspositionTable::default());
+
+    assert_eq!(best_move.unwrap().to_string(), "a1a8");
+    assert!(engine::is_mate_score(evaluation));
+    assert_eq!(engine::moves_to_mate(evaluation), 1);
+}
+
+/// Quiescence without any pruning: the static evaluation or the best capture sequence
+fn plain_quiescence(board: &Board) -> i32 {
+    board
+        .get_all_captures()
+        .map(|ply| -plain_quiescence(&board.make_move(ply)))
+        .fold(engine::evaluate(board), i32::max)
+}
+
+/// Negamax over every move, scoring the leaves the same way the alpha-beta search does
+fn negamax(board: &Board, depth: i32, ply_from_root: i32) -> i32 {
+    let moves: Vec<_> = board.get_all_moves().collect();
+    if moves.is_empty() {
+        return if board.is_in_check() {
+            -engine::CHECKMATE + ply_from_root
+        } else {
+            0
+        };
+    }
+    if depth == 0 {
+        return plain_quiescence(board);
+    }
+    moves
+        .into_iter()
+        .map(|ply| -negamax(&board.make_move(ply), depth - 1, ply_from_root + 1))
+        .max()
+        .unwrap()
+}
+
+#[test]
+fn alpha_beta_agrees_with_negamax() {
+    let positions = [
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3),
+        ("r3k3/8/2n5/3p4/4N3/2B5/8/4K2R w Kq - 0 1", 3),
+        ("6k1/5ppp/8/8/8/8/8/R6K w - - 0 1", 3),
+        ("k7/8/4p3/3p4/8/8/8/3Q3K w - - 0 1", 3),
+    ];
+
+    for (fen, depth) in positions {
+        let board = Board::new_from_fen(fen);
+        let (_, evaluation) = engine::search(&board, depth, &mut TranspositionTable::default());
+        assert_eq!(evaluation, negamax(&board, depth, 0), "{fen}");
+    }
+}
+
+#[test]
+fn iterative_deepening_respects_move_time() {
+    let board = Game::new().current_position().clone();
+    let limits = SearchLimits {
+        depth: None,
+        time_control: TimeControl::MoveTime(Duration::from_millis(100)),
+    };
+    let stop = AtomicBool::new(false);
+
+    let start = Instant::now();
+    let (best_move, _) = engine::iterative_deepening(
+        &board,
+        limits,
+        &mut TranspositionTable::default(),
+        &stop,
+        |_| {},
+    );
+
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(board.arbiter(&best_move.unwrap()));
+}
+
+#[test]
+fn iterative_deepening_stops_on_the_flag() {
+    let board = Game::new().current_position().clone();
+    let stop = AtomicBool::new(false);
+    let mut table = TranspositionTable::default();
+
+    let start = Instant::now();
+    let (best_move, _) = thread::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(Duration::from_millis(100));
+            stop.store(true, Ordering::SeqCst);
+        });
+        engine::iterative_deepening(&board, SearchLimits::default(), &mut table, &stop, |_| {})
+    });
+
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(board.arbiter(&best_move.unwrap()));
+}
+
+#[test]
+fn stopping_during_the_first_depth_still_gives_a_legal_move() {
+    let board =
+        Board::new_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let stop = AtomicBool::new(true);
+    let mut depths = 0;
+
+    let (best_move, _) = engine::iterative_deepening(
+        &board,
+        SearchLimits::default(),
+        &mut TranspositionTable::default(),
+        &stop,
+        |_| depths += 1,
+    );
+
+    assert_eq!(depths, 0);
+    assert!(board.arbiter(&best_move.unwrap()));
+}
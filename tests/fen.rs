@@ -0,0 +1,155 @@
+use chess_engine::{Board, Coord, FenError, Player, Ply};
+
+fn ply(origin: &str, destination: &str) -> Ply {
+    let coord = |s: &str| {
+        let mut chars = s.chars();
+        let col = chars.next().unwrap();
+        let row = chars.next().unwrap().to_digit(10).unwrap() as i32;
+        Board::notation_conversion(col, row).unwrap()
+    };
+
+    Ply {
+        origin: coord(origin),
+        destination: coord(destination),
+        promotion: None,
+    }
+}
+
+#[test]
+fn fen_round_trip() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        "6n1/p1qkp3/3p4/P3P3/5B2/2P1K3/7P/R7 b - - 37 61",
+    ];
+
+    for fen in fens {
+        assert_eq!(Board::new_from_fen(fen).to_fen(), fen);
+    }
+}
+
+#[test]
+fn full_move_number_from_fen() {
+    let board = Board::new_from_fen(
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    );
+    assert_eq!(board.full_move_number(), 10);
+}
+
+#[test]
+fn full_move_number_advances_after_black_moves() {
+    let board = Board::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+    let board = board.make_move(ply("e2", "e4"));
+    assert_eq!(board.full_move_number(), 1);
+    assert_eq!(
+        board.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+
+    let board = board.make_move(ply("g8", "f6"));
+    assert_eq!(board.full_move_number(), 2);
+    assert_eq!(
+        board.to_fen(),
+        "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2"
+    );
+}
+
+#[test]
+fn invalid_fens_are_rejected() {
+    let cases = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+            FenError::NotEnoughParts,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+            FenError::BadPlacement("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP".to_string()),
+        ),
+        (
+            "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            FenError::TooManyPieces("9".to_string()),
+        ),
+        (
+            "rnbqkbnrr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            FenError::TooManyPieces("rnbqkbnrr".to_string()),
+        ),
+        (
+            "4k3/ppppppppp/8/8/8/8/8/4K3 w - - 0 1",
+            FenError::TooManyPieces("ppppppppp".to_string()),
+        ),
+        (
+            "4k2/8/8/8/8/8/8/4K3 w - - 0 1",
+            FenError::BadPlacement("4k2".to_string()),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1",
+            FenError::UnknownPiece('X'),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            FenError::NoSuchSide("x".to_string()),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQxq - 0 1",
+            FenError::BadCastling("KQxq".to_string()),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq z9 0 1",
+            FenError::BadEnPassant("z9".to_string()),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq é 0 1",
+            FenError::BadEnPassant("é".to_string()),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1",
+            FenError::BadHalfmove("x".to_string()),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 x",
+            FenError::BadFullmove("x".to_string()),
+        ),
+        (
+            "rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1",
+            FenError::MissingKing(Player::Black),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBKKBNR w kq - 0 1",
+            FenError::TooManyKings(Player::White),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNP w kq - 0 1",
+            FenError::PawnOnBackRank(Coord { row: 0, col: 7 }),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq - 0 1",
+            FenError::InconsistentCastlingRights('K'),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1",
+            FenError::InconsistentEnPassant(Coord { row: 2, col: 4 }),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - a1 0 1",
+            FenError::InconsistentEnPassant(Coord { row: 0, col: 0 }),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 b - h8 0 1",
+            FenError::InconsistentEnPassant(Coord { row: 7, col: 7 }),
+        ),
+        (
+            "rnbqkbnr/ppppp1pp/8/5p1Q/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1",
+            FenError::OpponentInCheck,
+        ),
+    ];
+
+    for (fen, error) in cases {
+        assert_eq!(Board::from_fen(fen), Err(error), "{fen}");
+    }
+}
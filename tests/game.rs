@@ -0,0 +1,195 @@
+use chess_engine::{Board, Game, GameResult, Player, Termination};
+
+fn play(game: &mut Game, moves: &[&str]) {
+    for san in moves {
+        let ply = game.current_position().parse_san(san).unwrap();
+        assert!(game.make_move(ply));
+    }
+}
+
+#[test]
+fn undo_and_redo_step_through_the_game() {
+    let mut game = Game::new();
+    play(&mut game, &["e4", "e5", "Nf3"]);
+    let after_nf3 = game.current_position().clone();
+
+    assert_eq!(
+        game.undo().map(|ply| ply.to_string()),
+        Some("g1f3".to_string())
+    );
+    assert_eq!(
+        game.undo().map(|ply| ply.to_string()),
+        Some("e7e5".to_string())
+    );
+    assert_eq!(game.moves.len(), 1);
+    assert_eq!(game.states.len(), 2);
+    assert_eq!(
+        game.current_position().to_fen(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+
+    assert!(game.redo().is_some());
+    assert!(game.redo().is_some());
+    assert!(!game.can_redo());
+    assert_eq!(game.redo(), None);
+    assert_eq!(game.current_position(), &after_nf3);
+    assert_eq!(
+        game.last_move().map(|ply| ply.to_string()),
+        Some("g1f3".to_string())
+    );
+}
+
+#[test]
+fn undo_stops_at_the_start() {
+    let mut game = Game::new();
+    assert!(!game.can_undo());
+    assert_eq!(game.undo(), None);
+
+    play(&mut game, &["d4"]);
+    assert!(game.undo().is_some());
+    assert_eq!(game.undo(), None);
+    assert_eq!(game.states.len(), 1);
+}
+
+#[test]
+fn new_move_discards_redo_history() {
+    let mut game = Game::new();
+    play(&mut game, &["e4", "e5"]);
+    game.undo();
+    play(&mut game, &["c5"]);
+
+    assert!(!game.can_redo());
+    let history: Vec<String> = game
+        .history()
+        .map(|(board, ply)| board.ply_to_san(ply))
+        .collect();
+    assert_eq!(history, ["e4", "c5"]);
+}
+
+fn result(fen: &str) -> Option<GameResult> {
+    Game::from_fen(fen).unwrap().result()
+}
+
+#[test]
+fn result_reports_winner_and_reason() {
+    assert_eq!(Game::new().result(), None);
+    assert_eq!(
+        result("R5k1/5ppp/8/8/8/8/5PPP/6K1 b - - 1 1"),
+        Some(GameResult::win(Player::White, Termination::Checkmate))
+    );
+    assert_eq!(
+        result("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+        Some(GameResult::draw(Termination::Stalemate))
+    );
+    assert_eq!(
+        result("8/8/4k3/8/8/3K4/8/6N1 w - - 0 1"),
+        Some(GameResult::draw(Termination::InsufficientMaterial))
+    );
+    assert_eq!(
+        result("8/8/4k3/8/8/3K4/8/6R1 w - - 100 80"),
+        Some(GameResult::draw(Termination::FiftyMoveRule))
+    );
+    // Mate on the move that reaches the fifty-move limit still counts
+    assert_eq!(
+        result("R5k1/5ppp/8/8/8/8/5PPP/6K1 b - - 100 80"),
+        Some(GameResult::win(Player::White, Termination::Checkmate))
+    );
+}
+
+#[test]
+fn resignation_ends_the_game() {
+    let mut game = Game::new();
+    play(&mut game, &["e4"]);
+    game.resign(Player::Black);
+    let result = game.result().unwrap();
+    assert_eq!(
+        result,
+        GameResult::win(Player::White, Termination::Resignation)
+    );
+    assert_eq!(result.to_string(), "White wins by resignation");
+
+    game.undo();
+    assert_eq!(game.result(), None);
+}
+
+const KNIGHT_SHUFFLE: [&str; 4] = ["Nf3", "Nf6", "Ng1", "Ng8"];
+
+#[test]
+fn threefold_and_fivefold_repetition() {
+    let mut game = Game::new();
+    play(&mut game, &KNIGHT_SHUFFLE);
+    play(&mut game, &KNIGHT_SHUFFLE[..3]);
+    assert_eq!(game.repetition_count(), 2);
+    assert_eq!(game.result(), None);
+
+    play(&mut game, &KNIGHT_SHUFFLE[3..]);
+    assert_eq!(game.repetition_count(), 3);
+    assert!(game.verify_threefold_repetition());
+    let result = game.result().unwrap();
+    assert_eq!(result, GameResult::draw(Termination::ThreefoldRepetition));
+    assert_eq!(result.score(), "1/2-1/2");
+    assert_eq!(result.to_string(), "Draw by threefold repetition");
+
+    play(&mut game, &KNIGHT_SHUFFLE);
+    play(&mut game, &KNIGHT_SHUFFLE);
+    assert_eq!(game.repetition_count(), 5);
+    assert_eq!(
+        game.result(),
+        Some(GameResult::draw(Termination::FivefoldRepetition))
+    );
+}
+
+#[test]
+fn repetition_ignores_impossible_en_passant() {
+    // After 1. e4 no black pawn can capture en passant, so the position repeats
+    let mut game = Game::new();
+    play(
+        &mut game,
+        &["e4", "Nf6", "Nf3", "Ng8", "Ng1", "Nf6", "Nf3", "Ng8", "Ng1"],
+    );
+    assert_eq!(game.repetition_count(), 3);
+
+    // Here dxe3 is possible right after e4, so that position never occurs again
+    let mut game = Game::from_fen("1n2k3/8/8/8/3p4/8/4P3/4K1N1 w - - 0 1").unwrap();
+    play(
+        &mut game,
+        &["e4", "Nc6", "Nf3", "Nb8", "Ng1", "Nc6", "Nf3", "Nb8", "Ng1"],
+    );
+    assert_eq!(game.repetition_count(), 2);
+    assert_eq!(game.result(), None);
+}
+
+#[test]
+fn repetition_requires_same_castling_rights() {
+    let mut game = Game::new();
+    play(
+        &mut game,
+        &["Nf3", "Nf6", "Rg1", "Ng8", "Rh1", "Nf6", "Ng1", "Ng8"],
+    );
+    assert_eq!(game.current_position().board, game.states[0].board);
+    assert_eq!(game.repetition_count(), 1);
+}
+
+#[test]
+fn seventy_five_move_rule() {
+    assert_eq!(
+        result("8/8/4k3/8/8/3K4/8/6R1 w - - 150 120"),
+        Some(GameResult::draw(Termination::SeventyFiveMoveRule))
+    );
+    assert_eq!(
+        result("R5k1/5ppp/8/8/8/8/5PPP/6K1 b - - 150 120"),
+        Some(GameResult::win(Player::White, Termination::Checkmate))
+    );
+}
+
+#[test]
+fn pinned_en_passant_does_not_count() {
+    // exd6 would expose the king on h5 to the rook, so the en passant square is irrelevant
+    let with_en_passant = Board::new_from_fen("4k3/8/8/r2pP2K/8/8/8/8 w - d6 0 2");
+    let without = Board::new_from_fen("4k3/8/8/r2pP2K/8/8/8/8 w - - 0 40");
+    assert!(with_en_passant.is_same_position(&without));
+
+    let possible = Board::new_from_fen("4k3/8/8/3pP2K/8/8/8/8 w - d6 0 2");
+    let impossible = Board::new_from_fen("4k3/8/8/3pP2K/8/8/8/8 w - - 0 2");
+    assert!(!possible.is_same_position(&impossible));
+}
@@ -0,0 +1,124 @@
+use chess_engine::{Board, MoveFilter, Ply};
+
+const FENS: [&str; 5] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+];
+
+#[test]
+fn captures_and_quiet_moves_partition_all_moves() {
+    for fen in FENS {
+        let board = Board::new_from_fen(fen);
+        let all: Vec<_> = board.get_moves(MoveFilter::All).collect();
+        let captures: Vec<_> = board.get_all_captures().collect();
+        let quiet: Vec<_> = board.get_quiet_moves().collect();
+
+        assert_eq!(all.len(), captures.len() + quiet.len(), "{fen}");
+        assert!(captures.iter().all(|ply| all.contains(ply)), "{fen}");
+        assert!(quiet.iter().all(|ply| !captures.contains(ply)), "{fen}");
+    }
+}
+
+#[test]
+fn filtered_move_counts() {
+    // Captures (with promotions), quiet moves and checks, from the published perft breakdowns
+    let expected = [(0, 20, 0), (8, 40, 0), (1, 13, 2), (0, 6, 0), (6, 38, 0)];
+    assert_eq!(FENS.len(), expected.len());
+
+    for (fen, (captures, quiet, checks)) in FENS.into_iter().zip(expected) {
+        let board = Board::new_from_fen(fen);
+        assert_eq!(board.get_all_captures().count(), captures, "{fen}");
+        assert_eq!(board.get_quiet_moves().count(), quiet, "{fen}");
+        assert_eq!(board.get_checks().count(), checks, "{fen}");
+    }
+}
+
+/// Checks `gives_check` against actually playing every move, down to `depth` plies
+fn check_gives_check(board: &Board, depth: i32) {
+    if depth == 0 {
+        return;
+    }
+    for ply in board.get_all_moves() {
+        let next = board.make_move(ply);
+        assert_eq!(board.gives_check(ply), next.is_in_check(), "{ply}");
+        check_gives_check(&next, depth - 1);
+    }
+}
+
+#[test]
+fn gives_check_agrees_with_playing_the_move() {
+    for fen in FENS {
+        check_gives_check(&Board::new_from_fen(fen), 3);
+    }
+}
+
+#[test]
+fn pinned_pawn_can_push_towards_its_king() {
+    // The rook on c4 pins the pawn against the king in front of it
+    let board = Board::new_from_fen("4k3/2Kp4/8/2P5/2r5/8/8/8 w - - 5 4");
+    let moves: Vec<String> = board.get_all_moves().map(|ply| ply.to_string()).collect();
+
+    assert!(moves.contains(&"c5c6".to_string()));
+    assert_eq!(moves.len(), 6);
+}
+
+/// Plays every line to `depth` plies in place, checking that each move gives the same
+/// position as the copy-make API and that unmaking it restores the board exactly
+fn check_make_unmake(board: &mut Board, depth: i32) {
+    if depth == 0 {
+        return;
+    }
+    let moves: Vec<_> = board.get_all_moves().collect();
+    for ply in moves {
+        let before = board.clone();
+        let undo = board.make_move_mut(ply);
+        assert_eq!(
+            *board,
+            before.make_move(ply),
+            "{} after {ply}",
+            before.to_fen()
+        );
+        Board::check_everything(board);
+
+        check_make_unmake(board, depth - 1);
+        board.unmake_move(undo);
+        assert_eq!(*board, before, "{} after undoing {ply}", before.to_fen());
+    }
+}
+
+#[test]
+fn unmake_move_restores_the_position() {
+    for fen in FENS {
+        check_make_unmake(&mut Board::new_from_fen(fen), 3);
+    }
+}
+
+#[test]
+fn move_list_holds_the_most_moves_possible() {
+    // The position with the most legal moves known
+    let board = Board::new_from_fen("R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1");
+    assert_eq!(board.legal_moves().len(), 218);
+}
+
+#[test]
+fn move_list_picks_moves_by_score() {
+    let board = Board::new_from_fen(FENS[1]);
+    let mut moves = board.legal_moves();
+    assert_eq!(moves.len(), 48);
+
+    let square = |ply: Ply| ply.destination.row * 8 + ply.destination.col;
+    moves.set_scores(square);
+    let picked: Vec<Ply> = (0..moves.len()).map(|i| moves.pick_best(i)).collect();
+    assert_eq!(picked.len(), 48);
+    assert!(picked
+        .windows(2)
+        .all(|pair| square(pair[0]) >= square(pair[1])));
+
+    // Filtering keeps the order and the scores
+    moves.retain(|ply| board.is_capture(ply));
+    assert_eq!(moves.len(), 8);
+    assert!((1..moves.len()).all(|i| moves.score(i - 1) >= moves.score(i)));
+}
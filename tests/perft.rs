@@ -0,0 +1,148 @@
+use chess_engine::{
+    perft::{perft, perft_in_place},
+    Board,
+};
+
+// Positions and node counts from https://www.chessprogramming.org/Perft_Results and
+// Martin Sedlak's list of tricky positions
+const POSITIONS: [(&str, &[(i32, u64)]); 22] = [
+    (
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        &[
+            (1, 20),
+            (2, 400),
+            (3, 8902),
+            (4, 197281),
+            (5, 4865609),
+            (6, 119060324),
+        ],
+    ),
+    (
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &[(1, 48), (2, 2039), (3, 97862), (4, 4085603), (5, 193690690)],
+    ),
+    (
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        &[
+            (1, 14),
+            (2, 191),
+            (3, 2812),
+            (4, 43238),
+            (5, 674624),
+            (6, 11030083),
+        ],
+    ),
+    (
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        &[(1, 6), (2, 264), (3, 9467), (4, 422333), (5, 15833292)],
+    ),
+    // The same position with colors reversed
+    (
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+        &[(1, 6), (2, 264), (3, 9467), (4, 422333), (5, 15833292)],
+    ),
+    (
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        &[(1, 44), (2, 1486), (3, 62379), (4, 2103487), (5, 89941194)],
+    ),
+    (
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        &[(1, 46), (2, 2079), (3, 89890), (4, 3894594)],
+    ),
+    // Promotions on both sides, with and without captures
+    (
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        &[(1, 24), (2, 496), (3, 9483), (4, 182838), (5, 3605103)],
+    ),
+    // Illegal en passant captures
+    ("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", &[(6, 1134888)]),
+    ("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", &[(6, 1015133)]),
+    // En passant capture gives check
+    ("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", &[(6, 1440467)]),
+    // Castling gives check
+    ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", &[(6, 661072)]),
+    ("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", &[(6, 803711)]),
+    // Castling rights lost by captures on the rook squares, and castling through check
+    ("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", &[(4, 1274206)]),
+    ("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", &[(4, 1720476)]),
+    // Promotions out of check, giving check, and underpromotions
+    ("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", &[(6, 3821001)]),
+    ("4k3/1P6/8/8/8/8/K7/8 w - - 0 1", &[(6, 217342)]),
+    ("8/P1k5/K7/8/8/8/8/8 w - - 0 1", &[(6, 92683)]),
+    // Discovered check
+    ("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1", &[(5, 1004658)]),
+    // Stalemate and checkmate
+    ("K1k5/8/P7/8/8/8/8/8 w - - 0 1", &[(6, 2217)]),
+    ("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", &[(7, 567584)]),
+    ("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1", &[(4, 23527)]),
+];
+
+// Chess960 positions from https://www.chessprogramming.org/Chess960_Perft_Results, with
+// castling rights in Shredder-FEN
+const CHESS960_POSITIONS: [(&str, &[(i32, u64)]); 6] = [
+    (
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        &[(1, 21), (2, 528), (3, 12189), (4, 326672), (5, 8146062)],
+    ),
+    (
+        "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+        &[(1, 21), (2, 807), (3, 18002), (4, 667366), (5, 16253601)],
+    ),
+    (
+        "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+        &[(1, 20), (2, 479), (3, 10471), (4, 273318), (5, 6417013)],
+    ),
+    (
+        "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+        &[(1, 22), (2, 593), (3, 13440), (4, 382958), (5, 9183776)],
+    ),
+    (
+        "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+        &[(1, 28), (2, 1120), (3, 31058), (4, 1171749), (5, 34030312)],
+    ),
+    (
+        "qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9",
+        &[(1, 29), (2, 899), (3, 26578), (4, 824055), (5, 24851983)],
+    ),
+];
+
+// Depths with more nodes than this are only run with `cargo test -- --ignored`
+const FAST_NODE_LIMIT: u64 = 100_000;
+
+fn check_positions(include: impl Fn(u64) -> bool) {
+    for &(fen, checks) in POSITIONS.iter().chain(&CHESS960_POSITIONS) {
+        let board = Board::new_from_fen(fen);
+        for &(depth, nodes) in checks.iter().filter(|&&(_, nodes)| include(nodes)) {
+            assert_eq!(perft(&board, depth, 2), nodes, "{fen} at depth {depth}");
+        }
+    }
+}
+
+#[test]
+fn perft_fast() {
+    check_positions(|nodes| nodes <= FAST_NODE_LIMIT);
+}
+
+#[test]
+fn perft_in_place_matches_copy_make() {
+    for &(fen, checks) in POSITIONS.iter().chain(&CHESS960_POSITIONS) {
+        let mut board = Board::new_from_fen(fen);
+        for &(depth, nodes) in checks
+            .iter()
+            .filter(|&&(_, nodes)| nodes <= FAST_NODE_LIMIT)
+        {
+            assert_eq!(
+                perft_in_place(&mut board, depth),
+                nodes,
+                "{fen} at depth {depth}"
+            );
+        }
+        assert_eq!(board, Board::new_from_fen(fen));
+    }
+}
+
+#[test]
+#[ignore = "takes minutes, run with --release"]
+fn perft_deep() {
+    check_positions(|nodes| nodes > FAST_NODE_LIMIT);
+}
@@ -0,0 +1,191 @@
+use chess_engine::{
+    pgn::{pgn_games, read_pgn, read_pgn_games, PgnError},
+    FenError, Game, Player, SanError,
+};
+
+const FISCHER_SPASSKY: &str = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 {This opening is called the Ruy Lopez.}
+4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Nb8 10. d4 Nbd7
+11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4 15. Nb1 h6 16. Bh4 c5 17. dxe5
+Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6 21. Nc4 Nxc4 22. Bxc4 Nb6
+23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7 27. Qe3 Qg5 28. Qxg5
+hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5 33. f3 Bc8 34. Kf2 Bf5
+35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3 39. Kd2 Kb5 40. Rd6 Kc5 41. Ra6
+Nf2 42. g4 Bd3 43. Re6 1/2-1/2
+"#;
+
+#[test]
+fn reads_tags_and_main_line() {
+    let pgn = read_pgn(FISCHER_SPASSKY).unwrap();
+
+    assert_eq!(pgn.tags.len(), 7);
+    assert_eq!(pgn.tag("White"), Some("Fischer, Robert J."));
+    assert_eq!(pgn.tag("Round"), Some("29"));
+    assert_eq!(pgn.tag("Result"), Some("1/2-1/2"));
+
+    assert_eq!(pgn.states.len(), 86);
+    assert_eq!(
+        pgn.states.last().unwrap().to_fen(),
+        "8/8/4R1p1/2k3p1/1p4P1/1P1b1P2/3K1n2/8 b - - 2 43"
+    );
+}
+
+#[test]
+fn skips_comments_nags_and_variations() {
+    let pgn = read_pgn(
+        "1. e4 $1 {best by test} e5 (1... c5 2. Nf3 (2. c3) d6 {Najdorf?}) 2.Nf3 ; rest of line\n\
+         2... Nc6!? 3. Bb5 *",
+    )
+    .unwrap();
+
+    assert_eq!(pgn.tag("Result"), Some("*"));
+    assert_eq!(
+        pgn.states.last().unwrap().to_fen(),
+        "r1bqkbnr/pppp1ppp/2n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3"
+    );
+}
+
+#[test]
+fn starts_from_fen_tag() {
+    let pgn =
+        read_pgn("[SetUp \"1\"]\n[FEN \"6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1\"]\n\n1. Ra8# 1-0")
+            .unwrap();
+
+    assert_eq!(
+        pgn.states.last().unwrap().to_fen(),
+        "R5k1/5ppp/8/8/8/8/5PPP/6K1 b - - 1 1"
+    );
+}
+
+#[test]
+fn reads_multiple_games() {
+    let text = format!("{FISCHER_SPASSKY}\n[Event \"Second\"]\n\n1. d4 d5 0-1\n\n1. c4 *");
+    let games = read_pgn_games(&text).unwrap();
+
+    assert_eq!(games.len(), 3);
+    assert_eq!(games[1].tag("Event"), Some("Second"));
+    assert_eq!(games[1].states.len(), 3);
+    assert_eq!(games[2].states.len(), 2);
+}
+
+#[test]
+fn first_game_is_read_without_the_rest_of_the_file() {
+    let illegal = format!("{FISCHER_SPASSKY}\n[Event \"Broken\"]\n\n1. e4 e5 2. Ke3 *");
+    let unclosed = format!("{FISCHER_SPASSKY}\n[Event \"Broken\"]\n\n1. e4 {{unclosed");
+
+    for text in [illegal, unclosed] {
+        let game = read_pgn(&text).unwrap();
+        assert_eq!(game.tag("Event"), Some("F/S Return Match"));
+
+        let mut games = pgn_games(&text);
+        assert!(games.next().unwrap().is_ok());
+        assert!(games.next().unwrap().is_err());
+        assert!(read_pgn_games(&text).is_err());
+    }
+}
+
+#[test]
+fn reports_illegal_moves() {
+    assert_eq!(
+        read_pgn("1. e4 e5 2. Nf3 Nf6 3. Ke3").unwrap_err(),
+        PgnError::IllegalMove {
+            move_number: 3,
+            player: Player::White,
+            san: "Ke3".to_string(),
+            reason: SanError::IllegalMove("Ke3".to_string()),
+        }
+    );
+
+    let error = read_pgn("1. d4 d5 2. Nf3 Nf6 3. Nd2").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "move 3. Nd2: Nd2 matches more than one legal move"
+    );
+
+    assert_eq!(
+        read_pgn("1. e4 {unclosed").unwrap_err(),
+        PgnError::UnterminatedComment
+    );
+    assert_eq!(
+        read_pgn("1. e4 (1. d4").unwrap_err(),
+        PgnError::UnterminatedVariation
+    );
+}
+
+#[test]
+fn malformed_fen_tags_are_errors() {
+    assert_eq!(
+        read_pgn("[FEN \"rnbqkbnrr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\"]\n\n*")
+            .unwrap_err(),
+        PgnError::InvalidFen(FenError::TooManyPieces("rnbqkbnrr".to_string()))
+    );
+}
+
+#[test]
+fn writes_export_format() {
+    let pgn = read_pgn(FISCHER_SPASSKY).unwrap().to_pgn();
+
+    assert!(pgn.starts_with(
+        "[Event \"F/S Return Match\"]\n[Site \"Belgrade, Serbia JUG\"]\n[Date \"1992.11.04\"]\n"
+    ));
+    assert!(pgn.contains("[Result \"1/2-1/2\"]\n\n1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6"));
+    assert!(pgn.ends_with("42. g4 Bd3 43. Re6 1/2-1/2\n"));
+    assert!(pgn.lines().all(|line| line.len() < 80));
+
+    let game = read_pgn(&pgn).unwrap();
+    assert_eq!(game.tags.len(), 7);
+    assert_eq!(game.to_pgn(), pgn);
+}
+
+#[test]
+fn derives_result_from_final_position() {
+    let mut game = Game::new();
+    for san in ["f3", "e5", "g4", "Qh4#"] {
+        let ply = game.states.last().unwrap().parse_san(san).unwrap();
+        assert!(game.make_move(ply));
+    }
+    game.set_tag("White", "Fool");
+
+    assert_eq!(
+        game.to_pgn(),
+        "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+         [White \"Fool\"]\n[Black \"?\"]\n[Result \"0-1\"]\n[Termination \"normal\"]\n\n\
+         1. f3 e5 2. g4 Qh4# {Black wins by checkmate} 0-1\n"
+    );
+    assert_eq!(Game::new().to_pgn().lines().last(), Some("*"));
+}
+
+#[test]
+fn exports_set_up_positions() {
+    let mut game = Game::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 b - - 0 1").unwrap();
+    let ply = game.states[0].parse_san("h6").unwrap();
+    game.make_move(ply);
+    game.set_tag("Annotator", "Someone \"quoted\"");
+
+    let pgn = game.to_pgn();
+    assert!(pgn.contains(
+        "[SetUp \"1\"]\n[FEN \"6k1/5ppp/8/8/8/8/5PPP/R5K1 b - - 0 1\"]\n\
+         [Annotator \"Someone \\\"quoted\\\"\"]\n\n1... h6 *\n"
+    ));
+
+    let game = read_pgn(&pgn).unwrap();
+    assert_eq!(game.tag("Annotator"), Some("Someone \"quoted\""));
+    assert_eq!(game.moves.len(), 1);
+}
+
+#[test]
+fn resignation_is_exported() {
+    let mut game = read_pgn("1. e4 e5 2. Qh5 Nc6 3. Bc4 *").unwrap();
+    game.resign(Player::Black);
+
+    let pgn = game.to_pgn();
+    assert!(pgn.contains("[Result \"1-0\"]\n[Termination \"normal\"]\n"));
+    assert!(pgn.ends_with("3. Bc4 {White wins by resignation} 1-0\n"));
+}
@@ -0,0 +1,52 @@
+use chess_engine::{Board, Coord, Kind, ParsePlyError, Ply};
+
+#[test]
+fn uci_notation_round_trip() {
+    let ply = Ply {
+        origin: Coord { row: 6, col: 4 },
+        destination: Coord { row: 7, col: 4 },
+        promotion: Some(Kind::Queen),
+    };
+    assert_eq!(ply.to_string(), "e7e8q");
+    assert_eq!("e7e8q".parse::<Ply>(), Ok(ply));
+
+    let ply = Ply {
+        origin: Coord { row: 1, col: 4 },
+        destination: Coord { row: 3, col: 4 },
+        promotion: None,
+    };
+    assert_eq!(ply.to_string(), "e2e4");
+    assert_eq!("e2e4".parse::<Ply>(), Ok(ply));
+}
+
+#[test]
+fn uci_notation_syntax_errors() {
+    for s in ["", "e2", "e2e", "e2e4qq", "i2e4", "e9e4", "e7e8k", "e2-e4"] {
+        assert_eq!(
+            s.parse::<Ply>(),
+            Err(ParsePlyError::InvalidSyntax(s.to_string()))
+        );
+    }
+}
+
+#[test]
+fn uci_moves_are_checked_against_the_board() {
+    let board = Board::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+    assert!(board.parse_uci_move("g1f3").is_ok());
+    assert_eq!(
+        board.parse_uci_move("e2e5"),
+        Err(ParsePlyError::IllegalMove("e2e5".to_string()))
+    );
+    assert_eq!(
+        board.parse_uci_move("e7e5"),
+        Err(ParsePlyError::IllegalMove("e7e5".to_string()))
+    );
+
+    // Every generated move survives a round trip through its string form
+    let kiwipete =
+        Board::new_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    for ply in kiwipete.get_all_moves() {
+        assert_eq!(kiwipete.parse_uci_move(&ply.to_string()), Ok(ply));
+    }
+}
@@ -0,0 +1,87 @@
+use chess_engine::{Board, SanError};
+
+fn assert_san(fen: &str, san: &str) {
+    let board = Board::new_from_fen(fen);
+    let ply = board.parse_san(san).unwrap();
+    assert_eq!(board.ply_to_san(ply), san, "{fen}");
+}
+
+#[test]
+fn san_round_trip() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_san(start, "e4");
+    assert_san(start, "Nf3");
+
+    // Castling, captures and promotions
+    let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    assert_san(kiwipete, "O-O");
+    assert_san(kiwipete, "O-O-O");
+    assert_san(kiwipete, "dxe6");
+    assert_san(kiwipete, "Qxf6");
+    assert_san(kiwipete, "Bxa6");
+
+    let promotions = "r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1";
+    assert_san(promotions, "bxa8=Q+");
+    assert_san(promotions, "b8=N");
+
+    // En passant
+    assert_san(
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "exf6",
+    );
+}
+
+#[test]
+fn san_disambiguation() {
+    // Knights on b1 and f3 can both reach d2, rooks on a1 and a5 can both reach a3
+    let fen = "4k3/8/8/R7/8/8/8/RN2KB2 w - - 0 1";
+    assert_san(fen, "Rb5");
+    assert_san(fen, "R1a3");
+    assert_san(fen, "R5a3");
+
+    let fen = "4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1";
+    assert_san(fen, "Nbd2");
+    assert_san(fen, "Nfd2");
+
+    // With three queens, the one on a4 needs both its file and rank
+    let fen = "7k/8/8/8/Q1Q5/8/Q7/4K3 w - - 0 1";
+    assert_san(fen, "Qa4b3");
+    assert_san(fen, "Qcb3");
+    assert_san(fen, "Q2b3");
+}
+
+#[test]
+fn san_check_and_mate() {
+    let fen = "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1";
+    assert_san(fen, "Ra8#");
+
+    let fen = "6k1/5pp1/8/8/8/8/5PPP/R5K1 w - - 0 1";
+    assert_san(fen, "Ra8+");
+
+    // Annotations and check marks are ignored when parsing
+    let board = Board::new_from_fen(fen);
+    assert_eq!(board.parse_san("Ra8+!?"), board.parse_san("Ra8"));
+}
+
+#[test]
+fn san_errors() {
+    let start = Board::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(
+        start.parse_san("e5"),
+        Err(SanError::IllegalMove("e5".to_string()))
+    );
+    assert_eq!(
+        start.parse_san("O-O"),
+        Err(SanError::IllegalMove("O-O".to_string()))
+    );
+    assert_eq!(
+        start.parse_san("Zz9"),
+        Err(SanError::InvalidSyntax("Zz9".to_string()))
+    );
+
+    let board = Board::new_from_fen("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1");
+    assert_eq!(
+        board.parse_san("Nd2"),
+        Err(SanError::AmbiguousMove("Nd2".to_string()))
+    );
+}
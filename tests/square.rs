@@ -0,0 +1,98 @@
+use chess_engine::{Board, Coord, InvalidMoveError, Kind, Move, ParseSquareError, Ply, Square};
+
+#[test]
+fn square_names_and_coords() {
+    let e4: Square = "e4".parse().unwrap();
+    assert_eq!(e4.index(), 28);
+    assert_eq!((e4.file(), e4.rank()), (4, 3));
+    assert_eq!(e4.to_string(), "e4");
+    assert_eq!(Coord::from(e4), Coord { row: 3, col: 4 });
+    assert_eq!(Square::from_coord(Coord { row: 3, col: 4 }), Some(e4));
+
+    assert_eq!(Square::from_coord(Coord { row: 8, col: 0 }), None);
+    assert_eq!(Square::from_index(64), None);
+    assert_eq!("a1".parse(), Ok(Square::A1));
+    assert_eq!("h8".parse(), Ok(Square::H8));
+    for s in ["", "e", "i1", "a9", "e44", "E4"] {
+        assert_eq!(s.parse::<Square>(), Err(ParseSquareError(s.to_string())));
+    }
+
+    for index in 0..64 {
+        let square = Square::from_index(index).unwrap();
+        assert_eq!(square.to_string().parse(), Ok(square));
+        assert_eq!(Square::from_coord(square.coord()), Some(square));
+    }
+}
+
+#[test]
+fn packed_moves_keep_squares_and_flags() {
+    let (e7, d8) = ("e7".parse().unwrap(), "d8".parse().unwrap());
+    let promotion = Move::new_promotion(e7, d8, Kind::Knight, true);
+    assert_eq!((promotion.origin(), promotion.destination()), (e7, d8));
+    assert_eq!(promotion.promotion(), Some(Kind::Knight));
+    assert!(promotion.is_capture() && !promotion.is_en_passant() && !promotion.is_castle());
+    assert_eq!(promotion.to_string(), "e7d8n");
+
+    let quiet = Move::new(Square::A1, Square::H8, Move::QUIET).unwrap();
+    assert_eq!(u16::from(quiet), 63 << 6);
+    assert_eq!(quiet.promotion(), None);
+    assert!(!quiet.is_capture());
+}
+
+#[test]
+fn encoded_moves_match_the_board() {
+    let board =
+        Board::new_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let encode = |uci: &str| board.encode_move(uci.parse().unwrap());
+
+    assert_eq!(encode("e1g1").flags(), Move::KING_CASTLE);
+    assert_eq!(encode("e1c1").flags(), Move::QUEEN_CASTLE);
+    assert_eq!(encode("a2a4").flags(), Move::DOUBLE_PAWN_PUSH);
+    assert_eq!(encode("e5f7").flags(), Move::CAPTURE);
+    assert_eq!(encode("a2a3").flags(), Move::QUIET);
+
+    let en_passant =
+        Board::new_from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3");
+    let m = en_passant.encode_move("e5f6".parse().unwrap());
+    assert!(m.is_en_passant() && m.is_capture());
+
+    // Every move unpacks to the move it was made from, and the flags agree with the board
+    for ply in board.get_all_moves() {
+        let m = board.encode_move(ply);
+        assert_eq!(Ply::from(m), ply);
+        assert_eq!(m.is_capture(), board.is_capture(ply));
+    }
+}
+
+#[test]
+fn packed_moves_round_trip_through_u16() {
+    let fens = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "1rqbkrbn/1ppppp1p/1n6/p1N3p1/8/2P4P/PP1PPPP1/1RQBKRBN w FBfb - 0 9",
+    ];
+
+    for fen in fens {
+        let board = Board::new_from_fen(fen);
+        for ply in board.get_all_moves() {
+            let m = board.encode_move(ply);
+            assert_eq!(Move::try_from(u16::from(m)), Ok(m), "{fen} {ply}");
+        }
+    }
+
+    for flags in [6, 7] {
+        let bits = flags << 12 | 28;
+        assert_eq!(Move::try_from(bits), Err(InvalidMoveError(flags)));
+        assert_eq!(
+            Move::new(Square::A1, Square::H8, flags),
+            Err(InvalidMoveError(flags))
+        );
+    }
+    for flags in [Move::PROMOTION, 16, 0xffff] {
+        assert_eq!(
+            Move::new(Square::A1, Square::H8, flags),
+            Err(InvalidMoveError(flags))
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use chess_engine::{Board, Status};
+
+fn is_draw(fen: &str) -> bool {
+    matches!(Board::new_from_fen(fen).verify_status(), Status::Draw)
+}
+
+#[test]
+fn insufficient_material_is_a_draw() {
+    let drawn = [
+        "8/8/4k3/8/8/3K4/8/8 w - - 0 1",
+        "8/8/4k3/8/8/3K4/8/6N1 w - - 0 1",
+        "8/8/4k3/8/8/3K4/8/5B2 b - - 0 1",
+        "8/8/2b1k3/8/8/3K4/8/5B2 w - - 0 1",
+        // Any number of bishops, as long as they are all on the same color
+        "2b5/8/4k3/8/8/3K4/8/5B1B w - - 0 1",
+    ];
+    for fen in drawn {
+        assert!(
+            Board::new_from_fen(fen).has_insufficient_material(),
+            "{fen}"
+        );
+        assert!(is_draw(fen), "{fen}");
+    }
+}
+
+#[test]
+fn mating_material_is_not_a_draw() {
+    let ongoing = [
+        "8/8/4k3/8/8/3K4/4P3/8 w - - 0 1",
+        "8/8/4k3/8/8/3K4/8/6R1 w - - 0 1",
+        "8/8/4k3/8/8/3K4/8/4NN2 w - - 0 1",
+        "8/8/4k3/8/8/3K4/8/4BN2 w - - 0 1",
+        "8/8/3nk3/8/8/3K4/8/6N1 w - - 0 1",
+        // Bishops on opposite colors can still mate
+        "8/8/3bk3/8/8/3K4/8/5B2 w - - 0 1",
+    ];
+    for fen in ongoing {
+        assert!(
+            !Board::new_from_fen(fen).has_insufficient_material(),
+            "{fen}"
+        );
+        assert!(matches!(
+            Board::new_from_fen(fen).verify_status(),
+            Status::Ongoing
+        ));
+    }
+}
@@ -0,0 +1,122 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The engine binary running in UCI mode, with its output read on a separate thread so
+/// waiting for a reply can time out
+struct Engine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Engine {
+    fn start() -> Engine {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_chess-engine"))
+            .arg("uci")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Engine {
+            child,
+            stdin,
+            lines,
+        }
+    }
+
+    fn send(&mut self, command: &str) {
+        writeln!(self.stdin, "{command}").unwrap();
+    }
+
+    /// Every line printed up to and including the first one starting with `prefix`.
+    /// Panics if it doesn't come within `timeout`
+    fn read_until(&self, prefix: &str, timeout: Duration) -> Vec<String> {
+        let deadline = Instant::now() + timeout;
+        let mut lines = Vec::new();
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let line = self
+                .lines
+                .recv_timeout(remaining)
+                .unwrap_or_else(|_| panic!("no \"{prefix}\" within {timeout:?}, got {lines:?}"));
+            let done = line.starts_with(prefix);
+            lines.push(line);
+            if done {
+                return lines;
+            }
+        }
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "quit");
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn missing_own_clock_does_not_search_forever() {
+    let mut engine = Engine::start();
+    engine.send("position startpos moves e2e4");
+    // Black is to move, but only White's clock is given
+    engine.send("go wtime 60000 winc 1000");
+
+    let lines = engine.read_until("bestmove", Duration::from_secs(10));
+    assert_ne!(lines.last().unwrap(), "bestmove 0000");
+}
+
+#[test]
+fn chess960_option_castles_by_taking_the_rook() {
+    // Start position 518 is the standard one, so only the option says it's Chess960
+    let position =
+        "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQK2R w KQkq - 0 1 moves e1h1";
+
+    let mut engine = Engine::start();
+    engine.send("uci");
+    let lines = engine.read_until("uciok", Duration::from_secs(10));
+    assert!(lines.contains(&"option name UCI_Chess960 type check default false".to_string()));
+
+    engine.send(position);
+    engine.send("go depth 1");
+    let lines = engine.read_until("bestmove", Duration::from_secs(10));
+    assert!(lines[0].starts_with("info string invalid position"));
+
+    engine.send("setoption name UCI_Chess960 value true");
+    engine.send(position);
+    engine.send("go depth 1");
+    let lines = engine.read_until("bestmove", Duration::from_secs(10));
+    assert!(lines.iter().all(|line| !line.starts_with("info string")));
+
+    // The castle was played, so it's Black's move
+    let best_move = lines.last().unwrap().strip_prefix("bestmove ").unwrap();
+    assert!(matches!(&best_move[1..2], "7" | "8"), "{best_move}");
+}
+
+#[test]
+fn malformed_fen_is_reported_instead_of_crashing() {
+    let mut engine = Engine::start();
+    engine.send("position fen rnbqkbnrr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    engine.send("isready");
+
+    let lines = engine.read_until("readyok", Duration::from_secs(10));
+    assert!(lines[0].starts_with("info string invalid position"));
+}
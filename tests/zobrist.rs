@@ -0,0 +1,55 @@
+use chess_engine::Board;
+
+/// Walks every line to `depth` plies, checking that the incrementally updated hash
+/// always matches the hash computed from scratch
+fn check_hashes(board: &Board, depth: i32) {
+    Board::check_everything(board);
+    if depth == 0 {
+        return;
+    }
+    for ply in board.get_all_moves() {
+        check_hashes(&board.make_move(ply), depth - 1);
+    }
+}
+
+#[test]
+fn incremental_hash_matches_full_hash() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    ];
+
+    for fen in fens {
+        check_hashes(&Board::new_from_fen(fen), 3);
+    }
+}
+
+#[test]
+fn transpositions_hash_the_same() {
+    let start = Board::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let after_knights =
+        Board::new_from_fen("rnbqkb1r/pppppppp/5n2/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 2 2");
+    let with_black_to_move =
+        Board::new_from_fen("rnbqkb1r/pppppppp/5n2/8/8/5N2/PPPPPPPP/RNBQKB1R b KQkq - 2 2");
+    let without_castling =
+        Board::new_from_fen("rnbqkb1r/pppppppp/5n2/8/8/5N2/PPPPPPPP/RNBQKB1R w Qkq - 2 2");
+
+    assert_ne!(start.zobrist_hash(), after_knights.zobrist_hash());
+    assert_ne!(
+        after_knights.zobrist_hash(),
+        with_black_to_move.zobrist_hash()
+    );
+    assert_ne!(
+        after_knights.zobrist_hash(),
+        without_castling.zobrist_hash()
+    );
+
+    // The en passant square only matters if a capture is possible
+    let no_capture =
+        Board::new_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    let no_square =
+        Board::new_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+    assert_eq!(no_capture.zobrist_hash(), no_square.zobrist_hash());
+}